@@ -1,4 +1,10 @@
-use crate::{boundaries::Boundaries, collision, geometry::Aabb2};
+use crate::{
+    boundaries::Boundaries,
+    collision,
+    geometry::Aabb2,
+    interpolation::TransformInterpolation,
+    visuals::{Visual, Visuals},
+};
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use rand::Rng;
@@ -8,6 +14,16 @@ pub struct Ball {
     is_held: bool,
     /// Will knock a player out if hit.
     dangerous: bool,
+    /// The player who threw the ball, until it touches the ground.
+    thrower: Option<Thrower>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Thrower {
+    pub entity: Entity,
+    /// Kept with the ball, since the thrower may be knocked out or despawned
+    /// before it lands.
+    pub team: u8,
 }
 
 #[derive(Bundle)]
@@ -18,7 +34,7 @@ pub struct BallBundle {
     pub collider: Collider,
     pub collision_types: ActiveCollisionTypes,
     pub groups: CollisionGroups,
-    pub pbr: PbrBundle,
+    pub transform: TransformBundle,
 }
 
 impl BallBundle {
@@ -36,19 +52,17 @@ impl BallBundle {
             collision_types: ActiveCollisionTypes::default()
                 | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
             groups: Ball::ground_groups(),
-            pbr: PbrBundle {
-                mesh: ball_assets.mesh.clone(),
-                material: ball_assets.material.clone(),
-                transform: Transform::from_translation(position.clamp(bounds.min, bounds.max)),
-                ..default()
-            },
+            transform: TransformBundle::from_transform(Transform::from_translation(
+                position.clamp(bounds.min, bounds.max),
+            )),
         }
     }
 
-    fn new_thrown(ball_assets: &BallAssets, position: Vec3) -> Self {
+    fn new_thrown(ball_assets: &BallAssets, position: Vec3, thrower: Thrower) -> Self {
         Self {
             ball: Ball {
                 dangerous: true,
+                thrower: Some(thrower),
                 ..default()
             },
             body: RigidBody::Dynamic,
@@ -57,12 +71,7 @@ impl BallBundle {
             active_events: ActiveEvents::COLLISION_EVENTS,
             collision_types: ActiveCollisionTypes::default()
                 | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
-            pbr: PbrBundle {
-                mesh: ball_assets.mesh.clone(),
-                material: ball_assets.material.clone(),
-                transform: Transform::from_translation(position),
-                ..default()
-            },
+            transform: TransformBundle::from_transform(Transform::from_translation(position)),
         }
     }
 }
@@ -73,15 +82,16 @@ pub struct ThrownBallBundle {
     pub ccd: Ccd,
     pub damping: Damping,
     pub friction: Friction,
+    pub interpolation: TransformInterpolation,
     pub mass: ColliderMassProperties,
     pub restitution: Restitution,
     pub velocity: Velocity,
 }
 
 impl ThrownBallBundle {
-    pub fn new(ball_assets: &BallAssets, position: Vec3, velocity: Vec3) -> Self {
+    pub fn new(ball_assets: &BallAssets, position: Vec3, velocity: Vec3, thrower: Thrower) -> Self {
         Self {
-            ball: BallBundle::new_thrown(ball_assets, position),
+            ball: BallBundle::new_thrown(ball_assets, position, thrower),
             ccd: Ccd::enabled(),
             damping: Damping {
                 // Balls should come to rest eventually.
@@ -92,6 +102,7 @@ impl ThrownBallBundle {
                 coefficient: 0.7,
                 ..default()
             },
+            interpolation: default(),
             // Increased density for better impact.
             mass: ColliderMassProperties::Density(10.0),
             restitution: Restitution {
@@ -115,6 +126,15 @@ impl Ball {
         self.dangerous
     }
 
+    pub fn thrower(&self) -> Option<Thrower> {
+        self.thrower
+    }
+
+    /// On the ground and free for anyone to pick up.
+    pub fn is_loose(&self) -> bool {
+        !self.is_held && !self.dangerous
+    }
+
     pub fn ground_groups() -> CollisionGroups {
         CollisionGroups::new(
             collision::groups::GROUND_BALL,
@@ -150,16 +170,19 @@ impl Ball {
         bounds: &Boundaries,
         aabb: Aabb2,
         n_balls: u32,
+        rng: &mut impl Rng,
     ) {
-        let mut rng = rand::thread_rng();
         for _ in 0..n_balls {
             let x = rng.gen_range(aabb.min.x..aabb.max.x);
             let z = rng.gen_range(aabb.min.y..aabb.max.y);
-            commands.spawn(BallBundle::new_on_ground(
+            let mut ball = commands.spawn(BallBundle::new_on_ground(
                 ball_assets,
                 bounds,
                 Vec3::new(x, 0.0, z),
             ));
+            if let Some(visual) = &ball_assets.visual {
+                ball.insert(visual.bundle());
+            }
         }
     }
 
@@ -170,6 +193,8 @@ impl Ball {
         groups: &mut CollisionGroups,
     ) {
         self.is_held = true;
+        self.dangerous = false;
+        self.thrower = None;
         // TODO: this is going to cause flickering because the transform will be
         // applied before the player can become the parent
         transform.translation = Self::HELD_OFFSET;
@@ -180,30 +205,42 @@ impl Ball {
 
     pub fn on_touch_ground(&mut self) {
         self.dangerous = false;
+        self.thrower = None;
+    }
+
+    /// The ball can no longer knock anyone out, e.g. after it's blocked with a
+    /// held ball.
+    pub fn on_deflect(&mut self) {
+        self.dangerous = false;
+        self.thrower = None;
     }
 }
 
 #[derive(Resource)]
 pub struct BallAssets {
     pub radius: f32,
-    pub mesh: Handle<Mesh>,
-    pub material: Handle<StandardMaterial>,
+    /// `None` when running headless.
+    pub visual: Option<Visual>,
 }
 
 impl BallAssets {
-    pub fn new(meshes: &mut Assets<Mesh>, materials: &mut Assets<StandardMaterial>) -> Self {
+    pub fn new(visuals: Option<&mut Visuals>) -> Self {
         let radius = 0.2;
         Self {
             radius,
-            mesh: meshes.add(
-                shape::Icosphere {
-                    radius,
-                    subdivisions: 5,
-                }
-                .try_into()
-                .unwrap(),
-            ),
-            material: materials.add(Color::RED.into()),
+            visual: visuals.map(|visuals| {
+                Visual::new(
+                    visuals.meshes.add(
+                        shape::Icosphere {
+                            radius,
+                            subdivisions: 5,
+                        }
+                        .try_into()
+                        .unwrap(),
+                    ),
+                    visuals.materials.add(Color::RED.into()),
+                )
+            }),
         }
     }
 }
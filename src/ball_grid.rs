@@ -0,0 +1,140 @@
+use crate::{
+    ball::Ball,
+    geometry::{Aabb2, Circle},
+    grid2::Grid2,
+};
+use bevy::prelude::*;
+
+/// Loose balls on the ground, bucketed into a coarse grid so that players and
+/// squads can find balls near them.
+#[derive(Resource)]
+pub struct BallGrid {
+    pub balls_in_cell: Grid2<Vec<GroundBall>>,
+    pub cell_size: Vec2,
+    pub min: Vec2,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GroundBall {
+    pub entity: Entity,
+    pub position: Vec2,
+}
+
+impl BallGrid {
+    pub fn new(cell_size: Vec2, aabb: Aabb2) -> Self {
+        let pad = cell_size;
+        let grid_shape = ((aabb.shape() + 2.0 * pad) / cell_size).as_uvec2();
+        Self {
+            balls_in_cell: Grid2::new_fill(grid_shape, Vec::new()),
+            cell_size,
+            min: aabb.min - pad,
+        }
+    }
+
+    pub fn update(mut grid: ResMut<Self>, balls: Query<(Entity, &Ball, &GlobalTransform)>) {
+        for cell in grid.balls_in_cell.cells_mut() {
+            cell.clear();
+        }
+        for (entity, ball, tfm) in &balls {
+            if !ball.is_loose() {
+                continue;
+            }
+            let position = tfm.translation().xz();
+            let cell = grid.cell(position);
+            grid.balls_in_cell[cell].push(GroundBall { entity, position });
+        }
+    }
+
+    /// Up to `n` balls within `max_dist` of `position`, nearest first.
+    pub fn nearest(&self, position: Vec2, n: usize, max_dist: f32) -> Vec<GroundBall> {
+        let mut found = Vec::new();
+        if n == 0 {
+            return found;
+        }
+
+        // Search rings of cells around the center until the next ring can't
+        // have anything closer than what we already found.
+        let center = self.cell(position);
+        let max_ring = (max_dist / self.cell_size.min_element()).ceil() as i32 + 1;
+        for ring in 0..=max_ring {
+            let ring_dist = (ring - 1).max(0) as f32 * self.cell_size.min_element();
+            if ring_dist > max_dist {
+                break;
+            }
+            if found.len() >= n {
+                let nth_dist = found[n - 1].position.distance(position);
+                if ring_dist > nth_dist {
+                    break;
+                }
+            }
+            for cell in ring_cells(center, ring) {
+                if !self.contains_cell(cell) {
+                    continue;
+                }
+                found.extend(
+                    self.balls_in_cell[cell]
+                        .iter()
+                        .filter(|b| b.position.distance(position) <= max_dist),
+                );
+            }
+            found.sort_by(|a, b| {
+                a.position
+                    .distance_squared(position)
+                    .total_cmp(&b.position.distance_squared(position))
+            });
+        }
+        found.truncate(n);
+        found
+    }
+
+    /// All balls inside `circle`, in no particular order.
+    pub fn in_circle(&self, circle: Circle) -> impl Iterator<Item = GroundBall> + '_ {
+        let half = Vec2::splat(circle.radius);
+        self.in_cells(circle.center - half, circle.center + half)
+            .filter(move |b| b.position.distance(circle.center) <= circle.radius)
+    }
+
+    pub fn count_in_circle(&self, circle: Circle) -> usize {
+        self.in_circle(circle).count()
+    }
+
+    pub fn count_in_aabb(&self, aabb: Aabb2) -> usize {
+        self.in_cells(aabb.min, aabb.max)
+            .filter(|b| b.position.cmpge(aabb.min).all() && b.position.cmple(aabb.max).all())
+            .count()
+    }
+
+    /// Balls per square meter inside `circle`.
+    pub fn density(&self, circle: Circle) -> f32 {
+        let area = std::f32::consts::PI * circle.radius * circle.radius;
+        self.count_in_circle(circle) as f32 / area.max(f32::EPSILON)
+    }
+
+    /// Balls in the cells that overlap the box from `min` to `max`.
+    fn in_cells(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = GroundBall> + '_ {
+        let min_cell = self.cell(min).max(IVec2::ZERO);
+        let max_cell = self
+            .cell(max)
+            .min(self.balls_in_cell.shape().as_ivec2() - IVec2::ONE);
+        (min_cell.y..=max_cell.y)
+            .flat_map(move |y| (min_cell.x..=max_cell.x).map(move |x| IVec2::new(x, y)))
+            .flat_map(|cell| self.balls_in_cell[cell].iter().copied())
+    }
+
+    fn contains_cell(&self, cell: IVec2) -> bool {
+        cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.balls_in_cell.shape().as_ivec2()).all()
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        ((position - self.min) / self.cell_size).as_ivec2()
+    }
+}
+
+/// The cells on the square ring `ring` steps away from `center`.
+fn ring_cells(center: IVec2, ring: i32) -> impl Iterator<Item = IVec2> {
+    (-ring..=ring).flat_map(move |dy| {
+        (-ring..=ring)
+            .filter(move |&dx| dx.abs() == ring || dy.abs() == ring)
+            .map(move |dx| center + IVec2::new(dx, dy))
+    })
+}
@@ -0,0 +1,162 @@
+use crate::{
+    ball::Ball,
+    geometry::Aabb2,
+    grid2::Grid2,
+    parameters::{DODGE_LOOKAHEAD_SECONDS, DODGE_RADIUS},
+    team::AllTeamAssets,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+/// Predicted paths of dangerous balls, bucketed into a coarse grid so that
+/// each player only has to look at the balls passing nearby.
+#[derive(Resource)]
+pub struct BallPaths {
+    pub paths_in_cell: Grid2<Vec<BallPath>>,
+    pub cell_size: Vec2,
+    pub min: Vec2,
+}
+
+/// The lateral motion of a thrown ball while it's low enough to hit a player.
+#[derive(Clone, Copy)]
+pub struct BallPath {
+    ball: Entity,
+    start: Vec2,
+    velocity: Vec2,
+    /// Seconds from now when the ball comes down to player height.
+    enter: f32,
+    /// Seconds from now when the ball stops being a threat, or the end of the
+    /// lookahead.
+    exit: f32,
+}
+
+impl BallPaths {
+    pub fn new(cell_size: Vec2, aabb: Aabb2) -> Self {
+        let pad = cell_size;
+        let grid_shape = ((aabb.shape() + 2.0 * pad) / cell_size).as_uvec2();
+        Self {
+            paths_in_cell: Grid2::new_fill(grid_shape, Vec::new()),
+            cell_size,
+            min: aabb.min - pad,
+        }
+    }
+
+    pub fn update(
+        mut paths: ResMut<Self>,
+        team_assets: Res<AllTeamAssets>,
+        balls: Query<(Entity, &Ball, &GlobalTransform, &Velocity)>,
+    ) {
+        for cell in paths.paths_in_cell.cells_mut() {
+            cell.clear();
+        }
+
+        let max_height = team_assets
+            .teams
+            .iter()
+            .map(|t| t.size.y)
+            .fold(0.0, f32::max);
+        for (entity, ball, tfm, velocity) in &balls {
+            if !ball.is_dangerous() {
+                continue;
+            }
+            let position = tfm.translation();
+            let Some((enter, exit)) = time_below_height(position.y, velocity.linvel.y, max_height)
+            else {
+                continue;
+            };
+            let path = BallPath {
+                ball: entity,
+                start: position.xz(),
+                velocity: velocity.linvel.xz(),
+                enter,
+                exit,
+            };
+
+            // Walk the path in steps no longer than a cell, and add it to the
+            // cells around each step. Players only need to check their own
+            // cell.
+            let speed = path.velocity.length().max(f32::EPSILON);
+            let dt = paths.cell_size.min_element() / speed;
+            let mut t = enter;
+            loop {
+                let cell = paths.cell(path.at(t));
+                for dx in -1..=1 {
+                    for dz in -1..=1 {
+                        let paths_in_cell = &mut paths.paths_in_cell[cell + IVec2::new(dx, dz)];
+                        if paths_in_cell.last().map(|p| p.ball) != Some(entity) {
+                            paths_in_cell.push(path);
+                        }
+                    }
+                }
+                if t >= exit {
+                    break;
+                }
+                t = (t + dt).min(exit);
+            }
+        }
+    }
+
+    /// Which way a player at `position` should step to get out of the way of
+    /// incoming balls. The length grows with the danger.
+    pub fn sidestep(&self, position: Vec2) -> Vec2 {
+        let mut sidestep = Vec2::ZERO;
+        for path in &self.paths_in_cell[self.cell(position)] {
+            let t = path.closest_time(position);
+            let offset = position - path.at(t);
+            let dist = offset.length();
+            if dist >= DODGE_RADIUS {
+                continue;
+            }
+
+            // Step perpendicular to the path, away from it.
+            let perp = path.velocity.perp().normalize_or_zero();
+            let away = if perp.dot(offset) >= 0.0 { perp } else { -perp };
+            let urgency = 1.0 - t / DODGE_LOOKAHEAD_SECONDS;
+            sidestep += urgency * (1.0 - dist / DODGE_RADIUS) * away;
+        }
+        sidestep
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        ((position - self.min) / self.cell_size).as_ivec2()
+    }
+}
+
+impl BallPath {
+    fn at(&self, t: f32) -> Vec2 {
+        self.start + t * self.velocity
+    }
+
+    fn closest_time(&self, position: Vec2) -> f32 {
+        let speed_sq = self.velocity.length_squared();
+        if speed_sq < f32::EPSILON {
+            return self.enter;
+        }
+        ((position - self.start).dot(self.velocity) / speed_sq).clamp(self.enter, self.exit)
+    }
+}
+
+/// The first window within the lookahead when a ball on a ballistic
+/// trajectory is below `height`.
+fn time_below_height(y: f32, v_y: f32, height: f32) -> Option<(f32, f32)> {
+    let g = 9.80665;
+    let end = DODGE_LOOKAHEAD_SECONDS;
+
+    // y + v_y * t - (1/2)g * t^2 = height
+    let discriminant = v_y * v_y + 2.0 * g * (y - height);
+    if discriminant < 0.0 {
+        // The ball never gets that high.
+        return Some((0.0, end));
+    }
+    let sqrt_d = discriminant.sqrt();
+    let rise = (v_y - sqrt_d) / g;
+    let fall = (v_y + sqrt_d) / g;
+    if rise > 0.0 {
+        // Still on the way up.
+        Some((0.0, rise.min(end)))
+    } else if fall < end {
+        Some((fall.max(0.0), end))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,348 @@
+//! Plays headless bot-vs-bot matches between two squad configurations and
+//! prints the aggregate results.
+//!
+//! Config A plays as team 0 in even matches and team 1 in odd matches, so
+//! neither config gets an advantage from its spawn side.
+//!
+//! ```text
+//! cargo run --release --bin tournament -- --matches 20 --a-run-speed 12 --format json
+//! ```
+
+use mega_dodge_mayhem::{GameConfig, GameMode, HeadlessMatch, PlayerStats, TeamSize};
+use serde::Serialize;
+use std::{process::ExitCode, str::FromStr};
+
+const USAGE: &str = "\
+Usage: tournament [OPTIONS]
+
+Options:
+  --matches <N>               Number of matches to play [default: 10]
+  --seed <N>                  Seed of the first match; match i uses seed + i [default: 0]
+  --max-seconds <S>           Matches still running after this are draws [default: 300]
+  --ticks-per-second <N>      [default: 60]
+  --balls <N>                 [default: 300]
+  --catch-in                  Catches free jailed teammates
+  --a-squads <N>              Squads of config A [default: 2]
+  --a-players <N>             Players per squad of config A [default: 100]
+  --a-run-speed <F>           [default: 10]
+  --a-throw-distance <F>      [default: 15]
+  --a-throw-spread <F>        Radians [default: 0.3]
+  --a-catch-skill <F>         [default: 0.3]
+  --a-block-skill <F>         [default: 0.4]
+  --a-dodge <F>               [default: 0.5]
+  --b-squads, --b-players, --b-run-speed, --b-throw-distance, --b-throw-spread,
+  --b-catch-skill, --b-block-skill, --b-dodge
+                              Same as above, for config B
+  --format <csv|json>         [default: csv]
+  --per-match                 Also print a row for every match
+  -h, --help";
+
+#[derive(Clone, Copy, Serialize)]
+struct TeamConfig {
+    squads: u8,
+    players_per_squad: u32,
+    run_speed: f32,
+    throw_distance: f32,
+    throw_spread: f32,
+    catch_skill: f32,
+    block_skill: f32,
+    dodge: f32,
+}
+
+impl Default for TeamConfig {
+    fn default() -> Self {
+        let stats = PlayerStats::default();
+        let config = GameConfig::default();
+        Self {
+            squads: config.squads_per_team,
+            players_per_squad: config.players_per_squad,
+            run_speed: stats.run_speed,
+            throw_distance: stats.throw_distance,
+            throw_spread: stats.throw_spread,
+            catch_skill: stats.catch_skill,
+            block_skill: stats.block_skill,
+            dodge: stats.dodge,
+        }
+    }
+}
+
+impl TeamConfig {
+    fn size(&self) -> TeamSize {
+        TeamSize {
+            squads: self.squads,
+            players_per_squad: self.players_per_squad,
+        }
+    }
+
+    fn stats(&self) -> PlayerStats {
+        PlayerStats {
+            run_speed: self.run_speed,
+            throw_distance: self.throw_distance,
+            throw_spread: self.throw_spread,
+            catch_skill: self.catch_skill,
+            block_skill: self.block_skill,
+            dodge: self.dodge,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Args {
+    matches: u32,
+    seed: u64,
+    max_seconds: f32,
+    ticks_per_second: u32,
+    balls: u32,
+    catch_in: bool,
+    a: TeamConfig,
+    b: TeamConfig,
+    format: Format,
+    per_match: bool,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let config = GameConfig::default();
+        let mut args = Self {
+            matches: 10,
+            seed: 0,
+            max_seconds: 300.0,
+            ticks_per_second: config.ticks_per_second,
+            balls: config.n_balls,
+            catch_in: false,
+            a: TeamConfig::default(),
+            b: TeamConfig::default(),
+            format: Format::Csv,
+            per_match: false,
+        };
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(flag) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .ok_or_else(|| format!("missing value for {flag}"))
+            };
+            match flag.as_str() {
+                "--matches" => args.matches = parse(&flag, value()?)?,
+                "--seed" => args.seed = parse(&flag, value()?)?,
+                "--max-seconds" => args.max_seconds = parse_positive(&flag, value()?)?,
+                "--ticks-per-second" => args.ticks_per_second = parse_positive(&flag, value()?)?,
+                "--balls" => args.balls = parse(&flag, value()?)?,
+                "--a-squads" => args.a.squads = parse_positive(&flag, value()?)?,
+                "--a-players" => args.a.players_per_squad = parse_positive(&flag, value()?)?,
+                "--a-run-speed" => args.a.run_speed = parse(&flag, value()?)?,
+                "--a-throw-distance" => args.a.throw_distance = parse(&flag, value()?)?,
+                "--a-throw-spread" => args.a.throw_spread = parse(&flag, value()?)?,
+                "--a-catch-skill" => args.a.catch_skill = parse(&flag, value()?)?,
+                "--a-block-skill" => args.a.block_skill = parse(&flag, value()?)?,
+                "--a-dodge" => args.a.dodge = parse(&flag, value()?)?,
+                "--b-squads" => args.b.squads = parse_positive(&flag, value()?)?,
+                "--b-players" => args.b.players_per_squad = parse_positive(&flag, value()?)?,
+                "--b-run-speed" => args.b.run_speed = parse(&flag, value()?)?,
+                "--b-throw-distance" => args.b.throw_distance = parse(&flag, value()?)?,
+                "--b-throw-spread" => args.b.throw_spread = parse(&flag, value()?)?,
+                "--b-catch-skill" => args.b.catch_skill = parse(&flag, value()?)?,
+                "--b-block-skill" => args.b.block_skill = parse(&flag, value()?)?,
+                "--b-dodge" => args.b.dodge = parse(&flag, value()?)?,
+                "--format" => {
+                    args.format = match value()?.as_str() {
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        other => return Err(format!("unknown format {other}")),
+                    }
+                }
+                "--catch-in" => args.catch_in = true,
+                "--per-match" => args.per_match = true,
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                other => return Err(format!("unknown argument {other}")),
+            }
+        }
+        if u16::from(args.a.squads) + u16::from(args.b.squads) > u16::from(u8::MAX) {
+            return Err(format!("at most {} squads in total", u8::MAX));
+        }
+        Ok(args)
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {flag}: {value}"))
+}
+
+fn parse_positive<T: Default + FromStr + PartialOrd>(
+    flag: &str,
+    value: String,
+) -> Result<T, String> {
+    let parsed: T = parse(flag, value.clone())?;
+    if parsed > T::default() {
+        Ok(parsed)
+    } else {
+        Err(format!("{flag} must be positive: {value}"))
+    }
+}
+
+#[derive(Serialize)]
+struct MatchRow {
+    seed: u64,
+    a_team: u8,
+    /// "a", "b" or "draw".
+    winner: &'static str,
+    seconds: f32,
+    a_hits: u32,
+    b_hits: u32,
+    a_catches: u32,
+    b_catches: u32,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    matches: u32,
+    a: TeamConfig,
+    b: TeamConfig,
+    a_wins: u32,
+    b_wins: u32,
+    draws: u32,
+    a_win_rate: f32,
+    b_win_rate: f32,
+    avg_seconds: f32,
+    avg_a_hits: f32,
+    avg_b_hits: f32,
+    avg_a_catches: f32,
+    avg_b_catches: f32,
+}
+
+#[derive(Serialize)]
+struct Output<'a> {
+    summary: &'a Summary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matches: Option<&'a [MatchRow]>,
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut rows = Vec::with_capacity(args.matches as usize);
+    for i in 0..args.matches {
+        let seed = args.seed.wrapping_add(i as u64);
+        let a_team = (i % 2) as u8;
+        let mut team_sizes = [Some(args.a.size()), Some(args.b.size())];
+        let mut team_stats = [args.a.stats(), args.b.stats()];
+        if a_team == 1 {
+            team_sizes.swap(0, 1);
+            team_stats.swap(0, 1);
+        }
+        let config = GameConfig {
+            mode: GameMode::Match,
+            seed,
+            ticks_per_second: args.ticks_per_second,
+            n_balls: args.balls,
+            catch_in: args.catch_in,
+            team_sizes,
+            ..GameConfig::default()
+        };
+        let max_ticks = (args.max_seconds * args.ticks_per_second as f32) as u64;
+        let report = HeadlessMatch::new(config, team_stats).play(max_ticks);
+
+        let b_team = 1 - a_team;
+        let winner = match report.winner {
+            Some(t) if t == a_team => "a",
+            Some(_) => "b",
+            None => "draw",
+        };
+        rows.push(MatchRow {
+            seed,
+            a_team,
+            winner,
+            seconds: report.seconds,
+            a_hits: report.hits[a_team as usize],
+            b_hits: report.hits[b_team as usize],
+            a_catches: report.catches[a_team as usize],
+            b_catches: report.catches[b_team as usize],
+        });
+        eprintln!("match {}/{}: {winner}", i + 1, args.matches);
+    }
+
+    let count = |w| rows.iter().filter(|r| r.winner == w).count() as u32;
+    let n = args.matches.max(1) as f32;
+    let summary = Summary {
+        matches: args.matches,
+        a: args.a,
+        b: args.b,
+        a_wins: count("a"),
+        b_wins: count("b"),
+        draws: count("draw"),
+        a_win_rate: count("a") as f32 / n,
+        b_win_rate: count("b") as f32 / n,
+        avg_seconds: rows.iter().map(|r| r.seconds).sum::<f32>() / n,
+        avg_a_hits: rows.iter().map(|r| r.a_hits).sum::<u32>() as f32 / n,
+        avg_b_hits: rows.iter().map(|r| r.b_hits).sum::<u32>() as f32 / n,
+        avg_a_catches: rows.iter().map(|r| r.a_catches).sum::<u32>() as f32 / n,
+        avg_b_catches: rows.iter().map(|r| r.b_catches).sum::<u32>() as f32 / n,
+    };
+
+    match args.format {
+        Format::Json => {
+            let output = Output {
+                summary: &summary,
+                matches: args.per_match.then_some(rows.as_slice()),
+            };
+            match serde_json::to_string_pretty(&output) {
+                Ok(json) => println!("{json}"),
+                Err(e) => {
+                    eprintln!("Failed to serialize results: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Format::Csv => {
+            println!("matches,a_wins,b_wins,draws,a_win_rate,b_win_rate,avg_seconds,avg_a_hits,avg_b_hits,avg_a_catches,avg_b_catches");
+            println!(
+                "{},{},{},{},{:.3},{:.3},{:.2},{:.2},{:.2},{:.2},{:.2}",
+                summary.matches,
+                summary.a_wins,
+                summary.b_wins,
+                summary.draws,
+                summary.a_win_rate,
+                summary.b_win_rate,
+                summary.avg_seconds,
+                summary.avg_a_hits,
+                summary.avg_b_hits,
+                summary.avg_a_catches,
+                summary.avg_b_catches,
+            );
+            if args.per_match {
+                println!();
+                println!("seed,a_team,winner,seconds,a_hits,b_hits,a_catches,b_catches");
+                for r in &rows {
+                    println!(
+                        "{},{},{},{:.2},{},{},{},{}",
+                        r.seed,
+                        r.a_team,
+                        r.winner,
+                        r.seconds,
+                        r.a_hits,
+                        r.b_hits,
+                        r.a_catches,
+                        r.b_catches
+                    );
+                }
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
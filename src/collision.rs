@@ -1,12 +1,18 @@
 use crate::{
-    ball::Ball,
+    ball::{Ball, BallAssets},
+    boundaries::Boundaries,
     gym::Floor,
-    player::{KnockedOut, Player, PlayerBall, ThrowCooldown},
+    interpolation::TransformInterpolation,
+    match_rng::MatchRng,
+    parameters::BLOCK_FUMBLE_PROBABILITY,
+    player::{Jail, KnockedOut, Player, PlayerBall, ThrowCooldown},
     scoreboard::ScoreBoard,
+    squad::{Squad, SquadBehaviors},
     team::{AllTeamAssets, Team},
 };
 use bevy::prelude::*;
-use bevy_rapier3d::prelude::{Ccd, CollisionEvent, CollisionGroups, RigidBody};
+use bevy_rapier3d::prelude::{Ccd, CollisionEvent, CollisionGroups, RigidBody, Velocity};
+use rand::Rng;
 
 pub mod groups {
     use bevy_rapier3d::prelude::Group;
@@ -22,16 +28,23 @@ pub mod groups {
 pub fn handle_ball_player_collisions(
     mut commands: Commands,
     team_assets: Res<AllTeamAssets>,
+    ball_assets: Res<BallAssets>,
+    bounds: Res<Boundaries>,
+    behaviors: Res<SquadBehaviors>,
+    mut rng: ResMut<MatchRng>,
     mut scoreboard: ResMut<ScoreBoard>,
+    mut jail: Option<ResMut<Jail>>,
     mut events: EventReader<CollisionEvent>,
     mut players: Query<
         (
             &Team,
+            &Squad,
+            &GlobalTransform,
             &mut PlayerBall,
             &mut ThrowCooldown,
             &mut RigidBody,
             &mut CollisionGroups,
-            &mut Handle<StandardMaterial>,
+            Option<&mut Handle<StandardMaterial>>,
         ),
         (With<Player>, Without<KnockedOut>),
     >,
@@ -41,6 +54,7 @@ pub fn handle_ball_player_collisions(
             &mut Transform,
             &mut RigidBody,
             &mut CollisionGroups,
+            Option<&Velocity>,
         ),
         Without<Player>,
     >,
@@ -58,13 +72,15 @@ pub fn handle_ball_player_collisions(
             continue;
         }
 
-        let Ok((mut ball, mut ball_tfm, mut ball_body, mut ball_groups)) =
+        let Ok((mut ball, mut ball_tfm, mut ball_body, mut ball_groups, ball_velocity)) =
             balls.get_mut(ball_entity)
         else {
             continue;
         };
         let Ok((
             player_team,
+            player_squad,
+            player_tfm,
             mut player_ball,
             mut throw_cooldown,
             mut player_body,
@@ -74,23 +90,120 @@ pub fn handle_ball_player_collisions(
         else {
             continue;
         };
+        if *player_groups == Player::out_of_play_groups() {
+            // Knocked out earlier this tick, but `KnockedOut` isn't inserted
+            // yet.
+            continue;
+        }
 
         if ball.is_dangerous() {
             // Player got hit by thrown ball. Let's see if they can catch it.
-            // println!("player hit");
+            let stats = &behaviors.squads[player_squad.squad as usize].stats;
+            let ball_speed = ball_velocity.map_or(0.0, |v| v.linvel.length());
+            let catch_p = stats.catch_probability(player_ball.holding_ball, ball_speed);
+            let thrower = ball.thrower();
+            if rng.gen_bool(f64::from(catch_p)) {
+                if player_ball.holding_ball {
+                    // Catching needs a free hand, which is why it's harder. The
+                    // held ball drops and the caught one is kept.
+                    player_ball.drop_ball(
+                        &mut commands,
+                        &ball_assets,
+                        &bounds,
+                        player_entity,
+                        player_tfm.translation(),
+                    );
+                }
+                pick_up_ball(
+                    &mut commands,
+                    player_entity,
+                    &mut player_ball,
+                    &mut throw_cooldown,
+                    ball_entity,
+                    &mut ball,
+                    &mut ball_tfm,
+                    &mut ball_body,
+                    &mut ball_groups,
+                );
+
+                // As in real dodgeball, the thrower is out.
+                let catcher_team = player_team.team();
+                let Some(thrower) = thrower else {
+                    continue;
+                };
+                if thrower.team == catcher_team {
+                    // Caught a teammate's stray throw.
+                    continue;
+                }
+                if let Some(jail) = jail.as_deref_mut() {
+                    jail.catch_in(catcher_team);
+                }
+                let Ok((
+                    thrower_team,
+                    _,
+                    _,
+                    _,
+                    _,
+                    mut thrower_body,
+                    mut thrower_groups,
+                    mut thrower_material,
+                )) = players.get_mut(thrower.entity)
+                else {
+                    // The thrower is already out, so there's nobody to knock
+                    // out.
+                    continue;
+                };
+                if *thrower_groups == Player::out_of_play_groups() {
+                    continue;
+                }
+                scoreboard.team_catches[catcher_team as usize] += 1;
+                Player::knock_out(
+                    &mut commands,
+                    &team_assets,
+                    jail.as_deref_mut(),
+                    thrower.entity,
+                    thrower_team,
+                    &mut thrower_body,
+                    &mut thrower_groups,
+                    thrower_material.as_deref_mut(),
+                );
+                continue;
+            }
 
-            // Player failed to catch it, they are out.
+            if player_ball.holding_ball
+                && rng.gen_bool(f64::from(stats.block_skill.clamp(0.0, 1.0)))
+            {
+                // Blocked with the held ball. Rapier already bounced it off the
+                // player, and it can be picked up once it lands.
+                ball.on_deflect();
+                let blocker_team = player_team.team() as usize;
+                scoreboard.team_blocks[blocker_team] += 1;
+                if rng.gen_bool(f64::from(BLOCK_FUMBLE_PROBABILITY)) {
+                    scoreboard.team_fumbles[blocker_team] += 1;
+                    player_ball.drop_ball(
+                        &mut commands,
+                        &ball_assets,
+                        &bounds,
+                        player_entity,
+                        player_tfm.translation(),
+                    );
+                }
+                continue;
+            }
+
+            // Player failed to catch or block it, they are out.
             // HACK: Assumes there are only two teams.
             let other_team = (player_team.team() + 1) % 2;
             scoreboard.team_scores[other_team as usize] += 1;
             Player::knock_out(
                 &mut commands,
                 &team_assets,
+                jail.as_deref_mut(),
                 player_entity,
                 player_team,
                 &mut player_body,
                 &mut player_groups,
-                &mut player_material,
+                player_material.as_deref_mut(),
             );
         } else {
             // PERF: Using collision detection for ball pickup is the most
@@ -103,16 +216,44 @@ pub fn handle_ball_player_collisions(
             if ball.is_held() || player_ball.holding_ball {
                 // We can't steal the ball or hold multiple.
             } else {
-                // Take the ball.
-                ball.pick_up(&mut ball_tfm, &mut ball_body, &mut ball_groups);
-                player_ball.holding_ball = true;
-                throw_cooldown.timer.reset();
-                commands.entity(player_entity).add_child(ball_entity);
+                pick_up_ball(
+                    &mut commands,
+                    player_entity,
+                    &mut player_ball,
+                    &mut throw_cooldown,
+                    ball_entity,
+                    &mut ball,
+                    &mut ball_tfm,
+                    &mut ball_body,
+                    &mut ball_groups,
+                );
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+fn pick_up_ball(
+    commands: &mut Commands,
+    player_entity: Entity,
+    player_ball: &mut PlayerBall,
+    throw_cooldown: &mut ThrowCooldown,
+    ball_entity: Entity,
+    ball: &mut Ball,
+    ball_tfm: &mut Transform,
+    ball_body: &mut RigidBody,
+    ball_groups: &mut CollisionGroups,
+) {
+    ball.pick_up(ball_tfm, ball_body, ball_groups);
+    player_ball.holding_ball = true;
+    player_ball.stop_chasing();
+    throw_cooldown.timer.reset();
+    commands
+        .entity(ball_entity)
+        .remove::<TransformInterpolation>();
+    commands.entity(player_entity).add_child(ball_entity);
+}
+
 pub fn handle_ball_floor_collisions(
     mut commands: Commands,
     mut events: EventReader<CollisionEvent>,
@@ -0,0 +1,127 @@
+use crate::{
+    ball_grid::BallGrid,
+    environment::{Observation, SquadAction},
+    occupancy_grid::OccupancyGrid,
+    replay::Playback,
+    settings::GameConfig,
+    squad::{SquadBehaviors, SquadCommands, SquadStates},
+    tick::SimulationTick,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Gives orders to the squads of one team.
+///
+/// Orders are recorded in replays like the human's, so controllers don't run
+/// during playback.
+pub trait SquadController: Send + Sync {
+    /// Called once per tick. Orders take effect at the start of the next tick.
+    /// Orders for squads on other teams are ignored, as are orders that aren't
+    /// `SquadAction::is_valid`.
+    fn control(&mut self, team: u8, observation: &Observation) -> Vec<SquadAction>;
+}
+
+/// Who gives orders to a team's squads.
+#[derive(Default)]
+pub enum TeamController {
+    /// Orders come from picking and the squad UI, or from
+    /// `HeadlessMatch::queue_command`.
+    Human,
+    /// The heuristic bot in `opponent_ai`.
+    #[default]
+    Bot,
+    /// Spends stat points like a human team, and isn't affected by
+    /// `GameConfig::bot_difficulty`.
+    Custom(Box<dyn SquadController>),
+}
+
+impl TeamController {
+    pub fn kind(&self) -> ControllerKind {
+        match self {
+            Self::Human => ControllerKind::Human,
+            Self::Bot => ControllerKind::Bot,
+            Self::Custom(_) => ControllerKind::Custom,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ControllerKind {
+    Human,
+    Bot,
+    Custom,
+}
+
+/// The controller of each team.
+///
+/// Changes take effect when the next match starts.
+#[derive(Default, Resource)]
+pub struct TeamControllers {
+    pub teams: [TeamController; 2],
+}
+
+impl TeamControllers {
+    /// `team` is played by a human and the other team by the bot.
+    pub fn with_human(team: u8) -> Self {
+        let mut this = Self::default();
+        this.teams[team as usize] = TeamController::Human;
+        this
+    }
+
+    pub fn kind(&self, team: u8) -> ControllerKind {
+        self.teams[team as usize].kind()
+    }
+
+    pub fn kinds(&self) -> [ControllerKind; 2] {
+        [self.kind(0), self.kind(1)]
+    }
+
+    /// Lets each custom controller give orders to its team.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_custom(
+        mut controllers: ResMut<Self>,
+        config: Res<GameConfig>,
+        tick: Res<SimulationTick>,
+        occupancy: Res<OccupancyGrid>,
+        ball_grid: Res<BallGrid>,
+        behaviors: Res<SquadBehaviors>,
+        states: Res<SquadStates>,
+        playback: Option<Res<Playback>>,
+        mut commands: ResMut<SquadCommands>,
+    ) {
+        if playback.is_some()
+            || !controllers
+                .teams
+                .iter()
+                .any(|c| matches!(c, TeamController::Custom(_)))
+        {
+            return;
+        }
+
+        let squad_teams: Vec<_> = (0..states.squads.len())
+            .map(|squad| config.team_of_squad(squad as u8))
+            .collect();
+        for (team, controller) in (0..).zip(&mut controllers.teams) {
+            let TeamController::Custom(controller) = controller else {
+                continue;
+            };
+            let observation = Observation::new(
+                tick.0,
+                &states,
+                &behaviors,
+                &squad_teams,
+                &occupancy,
+                &ball_grid,
+            );
+            for action in controller.control(team, &observation) {
+                if squad_teams.get(action.squad as usize) != Some(&team) || !action.is_valid() {
+                    warn!("Ignoring action for squad {}", action.squad);
+                    continue;
+                }
+                for command in action.commands() {
+                    commands.queue(command);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use crate::{
+    ball_grid::BallGrid,
+    controller::TeamControllers,
+    headless::HeadlessMatch,
+    occupancy_grid::OccupancyGrid,
+    settings::{GameConfig, GameMode},
+    squad::{PlayerStats, SquadBehaviors, SquadCommand, SquadKnobs, SquadStates},
+};
+use bevy::prelude::*;
+
+/// A headless match wrapped as a training environment for squad commanders.
+///
+/// One team is commanded through `step`, while the other is played by the
+/// heuristic bot. Episodes are deterministic: the same seed and the same
+/// actions always produce the same observations and rewards.
+pub struct Environment {
+    config: EnvironmentConfig,
+    game: HeadlessMatch,
+    last_knockouts: [u32; 2],
+}
+
+/// Settings that stay the same across episodes.
+#[derive(Clone)]
+pub struct EnvironmentConfig {
+    /// The seed is replaced on every `reset`.
+    pub game: GameConfig,
+    /// The team commanded through `step`.
+    pub team: u8,
+    pub team_stats: [PlayerStats; 2],
+    /// Simulation ticks per call to `step`.
+    pub ticks_per_step: u32,
+    /// Episodes are cut off after this many ticks.
+    pub max_ticks: u64,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        let game = GameConfig {
+            mode: GameMode::Match,
+            ..default()
+        };
+        let max_ticks = 5 * 60 * u64::from(game.ticks_per_second);
+        Self {
+            game,
+            team: 0,
+            team_stats: default(),
+            ticks_per_step: 6,
+            max_ticks,
+        }
+    }
+}
+
+/// Orders for one squad, from `Environment::step` or a `SquadController`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SquadAction {
+    pub squad: u8,
+    pub leader_position: Option<Vec2>,
+    pub knobs: SquadKnobs,
+    /// See `SquadCommand::Volley`.
+    pub volley: bool,
+}
+
+impl SquadAction {
+    /// Policies can produce anything, so non-finite positions and bad knobs
+    /// are rejected before they reach the simulation.
+    pub fn is_valid(&self) -> bool {
+        self.leader_position.is_none_or(Vec2::is_finite) && self.knobs.is_valid()
+    }
+
+    /// The squad commands that carry out these orders.
+    pub fn commands(&self) -> impl Iterator<Item = SquadCommand> {
+        let squad = self.squad;
+        let go_to = self
+            .leader_position
+            .map(|position| SquadCommand::SetLeaderPosition {
+                squad,
+                position: position.to_array(),
+            });
+        let set_knobs = (self.knobs != SquadKnobs::default()).then_some(SquadCommand::SetKnobs {
+            squad,
+            knobs: self.knobs,
+        });
+        let volley = self.volley.then_some(SquadCommand::Volley { squad });
+        go_to.into_iter().chain(set_knobs).chain(volley)
+    }
+}
+
+pub struct Step {
+    pub observation: Observation,
+    /// Enemies knocked out minus teammates knocked out during this step.
+    pub reward: f32,
+    /// The episode is over and must be `reset`.
+    pub done: bool,
+    /// Only set when `done` and a team was eliminated.
+    pub winner: Option<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub tick: u64,
+    /// Indexed by squad.
+    pub squads: Vec<SquadObservation>,
+    /// Players per `OccupancyGrid` cell, in row-major order.
+    pub occupancy: Vec<u8>,
+    pub occupancy_shape: UVec2,
+    /// World XZ position of the minimum corner of the grid.
+    pub occupancy_min: Vec2,
+    pub occupancy_cell_size: Vec2,
+    /// Loose balls per `BallGrid` cell, in row-major order.
+    pub balls: Vec<u16>,
+    pub balls_shape: UVec2,
+    /// World XZ position of the minimum corner of the grid.
+    pub balls_min: Vec2,
+    pub balls_cell_size: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SquadObservation {
+    pub team: u8,
+    pub num_players: u32,
+    pub num_holding_balls: u32,
+    pub num_players_in_cluster: u32,
+    pub center_of_mass: Vec2,
+    pub cluster_radius: f32,
+    pub leader_position: Option<Vec2>,
+    pub throw_distance: f32,
+}
+
+impl Observation {
+    pub(crate) fn new(
+        tick: u64,
+        states: &SquadStates,
+        behaviors: &SquadBehaviors,
+        squad_teams: &[u8],
+        occupancy: &OccupancyGrid,
+        ball_grid: &BallGrid,
+    ) -> Self {
+        let squads = states
+            .squads
+            .iter()
+            .zip(&behaviors.squads)
+            .zip(squad_teams)
+            .map(|((state, behavior), &team)| SquadObservation {
+                team,
+                num_players: state.num_players,
+                num_holding_balls: state.num_holding_balls,
+                num_players_in_cluster: state.num_players_in_cluster,
+                center_of_mass: state.center_of_mass.xz(),
+                cluster_radius: state.cluster_radius,
+                leader_position: behavior.leader_position,
+                throw_distance: behavior.stats.throw_distance,
+            })
+            .collect();
+        let balls = ball_grid
+            .balls_in_cell
+            .cells()
+            .iter()
+            .map(|cell| cell.len().min(usize::from(u16::MAX)) as u16)
+            .collect();
+        Self {
+            tick,
+            squads,
+            occupancy: occupancy.players_in_cell.cells().to_vec(),
+            occupancy_shape: occupancy.players_in_cell.shape(),
+            occupancy_min: occupancy.min,
+            occupancy_cell_size: occupancy.cell_size,
+            balls,
+            balls_shape: ball_grid.balls_in_cell.shape(),
+            balls_min: ball_grid.min,
+            balls_cell_size: ball_grid.cell_size,
+        }
+    }
+}
+
+impl Environment {
+    /// Starts the first episode with `config.game.seed`.
+    pub fn new(config: EnvironmentConfig) -> Self {
+        let game = HeadlessMatch::with_controllers(
+            config.game.clone(),
+            config.team_stats,
+            TeamControllers::with_human(config.team),
+        );
+        Self {
+            config,
+            game,
+            last_knockouts: [0; 2],
+        }
+    }
+
+    pub fn config(&self) -> &EnvironmentConfig {
+        &self.config
+    }
+
+    /// The underlying match, e.g. for inspecting the ECS world.
+    pub fn game(&self) -> &HeadlessMatch {
+        &self.game
+    }
+
+    /// Starts a new episode.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.config.game.seed = seed;
+        self.game.restart(self.config.game.clone());
+        self.last_knockouts = [0; 2];
+        self.observe()
+    }
+
+    /// Applies `actions` and runs `ticks_per_step` ticks, or fewer if the
+    /// episode ends first.
+    ///
+    /// Actions for squads that aren't on the commanded team are ignored, as
+    /// are actions that aren't `SquadAction::is_valid`.
+    pub fn step(&mut self, actions: &[SquadAction]) -> Step {
+        for action in actions {
+            let team = self.game.squad_teams().get(action.squad as usize);
+            if team != Some(&self.config.team) || !action.is_valid() {
+                warn!("Ignoring action for squad {}", action.squad);
+                continue;
+            }
+            for command in action.commands() {
+                self.game.queue_command(command);
+            }
+        }
+
+        let mut outcome = self.game.outcome();
+        for _ in 0..self.config.ticks_per_step {
+            if outcome.is_some() || self.game.tick() >= self.config.max_ticks {
+                break;
+            }
+            self.game.run_ticks(1);
+            outcome = self.game.outcome();
+        }
+
+        let knockouts = self.game.knockouts();
+        let team = self.config.team as usize;
+        let enemy = 1 - team;
+        let scored = knockouts[team] - self.last_knockouts[team];
+        let conceded = knockouts[enemy] - self.last_knockouts[enemy];
+        self.last_knockouts = knockouts;
+
+        Step {
+            observation: self.observe(),
+            reward: scored as f32 - conceded as f32,
+            done: outcome.is_some() || self.game.tick() >= self.config.max_ticks,
+            winner: outcome.flatten(),
+        }
+    }
+
+    pub fn observe(&self) -> Observation {
+        let world = self.game.world();
+        Observation::new(
+            self.game.tick(),
+            world.resource::<SquadStates>(),
+            world.resource::<SquadBehaviors>(),
+            self.game.squad_teams(),
+            world.resource::<OccupancyGrid>(),
+            world.resource::<BallGrid>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formation::Formation;
+
+    fn actions(step: u32) -> Vec<SquadAction> {
+        match step {
+            0 => vec![SquadAction {
+                squad: 0,
+                leader_position: Some(Vec2::new(0.0, 10.0)),
+                ..default()
+            }],
+            100 => vec![SquadAction {
+                squad: 1,
+                knobs: SquadKnobs {
+                    formation: Some(Formation::Line),
+                    ..default()
+                },
+                ..default()
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reset_matches_new() {
+        let config = EnvironmentConfig {
+            ticks_per_step: 1,
+            ..default()
+        };
+        let mut reset = Environment::new(config.clone());
+        for step in 0..300 {
+            reset.step(&actions(step));
+        }
+        let first = reset.reset(9);
+
+        let mut new = Environment::new(EnvironmentConfig {
+            game: GameConfig {
+                seed: 9,
+                ..config.game
+            },
+            ..config
+        });
+        assert_eq!(first, new.observe());
+        for step in 0..1200 {
+            let (a, b) = (reset.step(&actions(step)), new.step(&actions(step)));
+            assert_eq!(a.observation, b.observation, "step {step}");
+            assert_eq!((a.reward, a.done), (b.reward, b.done), "step {step}");
+        }
+    }
+}
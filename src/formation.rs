@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{SQRT_2, TAU};
+
+/// The shape of a squad's cluster around its leader token.
+///
+/// Except for `Disc`, every player is assigned a slot, which is an offset from
+/// the leader that rotates with the squad's facing.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Formation {
+    /// A loose crowd without slots.
+    #[default]
+    Disc,
+    /// Wide and shallow.
+    Line,
+    /// Narrow and deep.
+    Column,
+    /// A triangle pointing forward.
+    Wedge,
+    /// Concentric circles around an empty middle.
+    Ring,
+    /// Concentric squares around an empty middle.
+    Box,
+}
+
+/// Players are numbered in spawn order. `SquadStates::update` keeps that order
+/// and closes the gaps left by knocked out players, so slots stay compact
+/// without reshuffling the formation. Ball carriers are moved ahead of
+/// everyone else, into the front rows or the inner layer of a `Ring` or `Box`.
+#[derive(Component, Default)]
+pub struct FormationSlot {
+    pub index: u32,
+}
+
+impl Formation {
+    pub const ALL: [Self; 6] = [
+        Self::Disc,
+        Self::Line,
+        Self::Column,
+        Self::Wedge,
+        Self::Ring,
+        Self::Box,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Disc => "Disc",
+            Self::Line => "Line",
+            Self::Column => "Column",
+            Self::Wedge => "Wedge",
+            Self::Ring => "Ring",
+            Self::Box => "Box",
+        }
+    }
+
+    /// The offset from the leader of slot `index` in a squad of `n` players.
+    /// `facing` is the angle of the squad's forward direction on the XZ plane.
+    ///
+    /// Returns `None` for `Disc`.
+    pub fn slot_offset(self, index: u32, n: u32, spacing: f32, facing: f32) -> Option<Vec2> {
+        let n = n.max(1);
+        let index = index.min(n - 1);
+
+        // X is forward and Y is to the left, in units of `spacing`.
+        let local = match self {
+            Self::Disc => return None,
+            Self::Line => grid_slot(index, n, LINE_ASPECT),
+            Self::Column => grid_slot(index, n, LINE_ASPECT.recip()),
+            Self::Wedge => wedge_slot(index, n),
+            Self::Ring => layered_slot(index, n, inner_size(n), ring_capacity, |size, f| {
+                size * Vec2::from_angle(TAU * f)
+            }),
+            Self::Box => layered_slot(index, n, inner_size(n), box_capacity, box_position),
+        };
+        Some(spacing * Vec2::from_angle(facing).rotate(local))
+    }
+
+    /// The distance from the leader to the furthest slot, or `None` for `Disc`.
+    pub fn radius(self, n: u32, spacing: f32) -> Option<f32> {
+        let n = n.max(1);
+        let local = match self {
+            Self::Disc => return None,
+            Self::Line | Self::Column => {
+                let aspect = if self == Self::Line {
+                    LINE_ASPECT
+                } else {
+                    LINE_ASPECT.recip()
+                };
+                let (rows, cols) = grid_shape(n, aspect);
+                0.5 * Vec2::new(rows as f32 - 1.0, cols as f32 - 1.0).length()
+            }
+            Self::Wedge => {
+                let rows = wedge_rows(n) as f32;
+                Vec2::new(0.5 * (rows - 1.0), rows - 1.0).length()
+            }
+            Self::Ring => outer_size(n, inner_size(n), ring_capacity),
+            Self::Box => SQRT_2 * outer_size(n, inner_size(n), box_capacity),
+        };
+        Some(spacing * local)
+    }
+}
+
+/// The distance between neighboring slots for a cluster density in players
+/// per square meter.
+pub fn slot_spacing(density: f32) -> f32 {
+    density.max(f32::EPSILON).recip().sqrt()
+}
+
+/// The angle to use as a squad's facing so it points along `dir`.
+pub fn facing_along(dir: Vec2) -> f32 {
+    dir.y.atan2(dir.x)
+}
+
+/// Columns per row of a `Line`.
+const LINE_ASPECT: f32 = 8.0;
+
+fn grid_shape(n: u32, aspect: f32) -> (u32, u32) {
+    let cols = ((n as f32 * aspect).sqrt().ceil() as u32).clamp(1, n);
+    let rows = n.div_ceil(cols);
+    (rows, cols)
+}
+
+/// Rows from front to back, with a partial last row centered.
+fn grid_slot(index: u32, n: u32, aspect: f32) -> Vec2 {
+    let (rows, cols) = grid_shape(n, aspect);
+    let row = index / cols;
+    let col = index % cols;
+    let cols_in_row = cols.min(n - row * cols);
+    Vec2::new(
+        0.5 * (rows - 1) as f32 - row as f32,
+        col as f32 - 0.5 * (cols_in_row - 1) as f32,
+    )
+}
+
+fn wedge_rows(n: u32) -> u32 {
+    (n as f32).sqrt().ceil() as u32
+}
+
+/// Row `r` holds `2r + 1` players, so the first `r` rows hold `r^2`.
+fn wedge_slot(index: u32, n: u32) -> Vec2 {
+    let rows = wedge_rows(n);
+    let row = (index as f32).sqrt() as u32;
+    let first = row * row;
+    let width = (2 * row + 1).min(n - first);
+    Vec2::new(
+        0.5 * (rows - 1) as f32 - row as f32,
+        (index - first) as f32 - 0.5 * (width - 1) as f32,
+    )
+}
+
+/// Leaves the middle of a `Ring` or `Box` empty.
+fn inner_size(n: u32) -> f32 {
+    (0.6 * (n as f32 / std::f32::consts::PI).sqrt()).max(1.0)
+}
+
+fn ring_capacity(radius: f32) -> u32 {
+    (TAU * radius) as u32
+}
+
+fn box_capacity(half_size: f32) -> u32 {
+    (8.0 * half_size) as u32
+}
+
+/// `f` is how far around the perimeter the slot is, from 0 to 1.
+fn box_position(half_size: f32, f: f32) -> Vec2 {
+    let side_len = 2.0 * half_size;
+    let t = 4.0 * side_len * f;
+    let along = t % side_len;
+    match (t / side_len) as u32 {
+        0 => Vec2::new(half_size, along - half_size),
+        1 => Vec2::new(half_size - along, half_size),
+        2 => Vec2::new(-half_size, half_size - along),
+        _ => Vec2::new(along - half_size, -half_size),
+    }
+}
+
+/// Fills layers from the inside out, one slot further apart each layer.
+fn layered_slot(
+    index: u32,
+    n: u32,
+    inner_size: f32,
+    capacity: impl Fn(f32) -> u32,
+    position: impl Fn(f32, f32) -> Vec2,
+) -> Vec2 {
+    let mut size = inner_size;
+    let mut first = 0;
+    loop {
+        let remaining = n - first;
+        let in_layer = capacity(size).max(1).min(remaining);
+        if index < first + in_layer {
+            return position(size, (index - first) as f32 / in_layer as f32);
+        }
+        first += in_layer;
+        size += 1.0;
+    }
+}
+
+fn outer_size(n: u32, inner_size: f32, capacity: impl Fn(f32) -> u32) -> f32 {
+    let mut size = inner_size;
+    let mut placed = capacity(size).max(1);
+    while placed < n {
+        size += 1.0;
+        placed += capacity(size).max(1);
+    }
+    size
+}
@@ -0,0 +1,76 @@
+use crate::{
+    game_ui::GameUi, match_progress::MatchProgress, restart_game::load_game, settings::GameSettings,
+};
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+
+/// The top-level state of the app.
+///
+/// The simulation only runs while `Playing`. Restarting always goes through
+/// `Loading`, which replaces the scene with a new match.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, States)]
+pub enum GameState {
+    /// The scene spawned at startup is frozen behind the menu.
+    #[default]
+    MainMenu,
+    Loading,
+    Playing,
+    Paused,
+    /// The match is finished and the results are shown.
+    GameOver,
+}
+
+impl GameState {
+    /// Adds the transitions shared by windowed and headless apps.
+    pub fn add_to(app: &mut App) {
+        app.add_state::<Self>().add_systems(
+            OnEnter(Self::Loading),
+            (load_game, Self::finish_loading).chain(),
+        );
+    }
+
+    /// Run condition for the simulation.
+    ///
+    /// A finished match also stops the ticks that remain in the current frame,
+    /// before the transition to `GameOver` is applied.
+    pub fn is_simulating(state: Res<State<Self>>, progress: Res<MatchProgress>) -> bool {
+        *state.get() == Self::Playing && !progress.finished
+    }
+
+    fn finish_loading(mut next_state: ResMut<NextState<Self>>) {
+        next_state.set(Self::Playing);
+    }
+
+    /// Applies a transition right away, instead of waiting for the next frame.
+    pub fn transition_now(world: &mut World, state: Self) {
+        world.resource_mut::<NextState<Self>>().set(state);
+        world.run_system_once(apply_state_transition::<Self>);
+    }
+
+    pub fn toggle_pause(
+        key: Res<Input<KeyCode>>,
+        state: Res<State<Self>>,
+        mut next_state: ResMut<NextState<Self>>,
+    ) {
+        if !key.just_pressed(KeyCode::P) {
+            return;
+        }
+        match state.get() {
+            Self::Playing => next_state.set(Self::Paused),
+            Self::Paused => next_state.set(Self::Playing),
+            _ => {}
+        }
+    }
+
+    pub fn enter_main_menu(mut game_ui: ResMut<GameUi>) {
+        game_ui.show = true;
+    }
+
+    pub fn exit_main_menu(settings: Res<GameSettings>, mut game_ui: ResMut<GameUi>) {
+        game_ui.show = !settings.hide_menu_when_game_starts;
+    }
+
+    /// Makes room for the results.
+    pub fn enter_game_over(mut game_ui: ResMut<GameUi>) {
+        game_ui.show = false;
+    }
+}
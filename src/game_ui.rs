@@ -1,19 +1,30 @@
 use crate::{
+    game_state::GameState,
+    opponent_ai::BotDifficulty,
+    point_buy::StatPreset,
+    replay::{Replay, ReplayRecorder, WatchReplay},
     restart_game::RestartGame,
-    settings::{GameMode, GameSettings, SaveSettings},
+    settings::{GameConfig, GameMode, GameSettings, SaveSettings},
+    tick::SimulationTick,
+    time_controls::{TimeControls, SPEEDS},
 };
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 use smooth_bevy_cameras::controllers::orbit::OrbitCameraController;
+use std::path::PathBuf;
 
 #[derive(Resource)]
 pub struct GameUi {
     pub show: bool,
+    pub replay_files: Vec<PathBuf>,
 }
 
 impl Default for GameUi {
     fn default() -> Self {
-        Self { show: true }
+        Self {
+            show: true,
+            replay_files: Replay::list_files(),
+        }
     }
 }
 
@@ -24,11 +35,18 @@ impl GameUi {
         }
     }
 
+    #[allow(clippy::complexity)]
     pub fn update(
         mut commands: Commands,
         mut save_events: EventWriter<SaveSettings>,
         mut game_ui: ResMut<Self>,
         mut settings: ResMut<GameSettings>,
+        config: Res<GameConfig>,
+        recorder: Res<ReplayRecorder>,
+        tick: Res<SimulationTick>,
+        state: Res<State<GameState>>,
+        mut next_state: ResMut<NextState<GameState>>,
+        mut time_controls: ResMut<TimeControls>,
         mut contexts: EguiContexts,
         mut cameras: Query<&mut OrbitCameraController>,
     ) {
@@ -44,10 +62,33 @@ impl GameUi {
             .collapsible(false)
             .show(contexts.ctx_mut(), |ui| {
                 ui.vertical_centered(|ui| {
-                    if ui.button("Restart Game").clicked() {
+                    let start_text = if *state.get() == GameState::MainMenu {
+                        "Start Game"
+                    } else {
+                        "Restart Game"
+                    };
+                    if ui.button(start_text).clicked() {
                         game_ui.show = !settings.hide_menu_when_game_starts;
+                        settings.prepare_next_game();
                         commands.add(RestartGame);
                     }
+                    match state.get() {
+                        GameState::Playing if ui.button("Pause").clicked() => {
+                            next_state.set(GameState::Paused);
+                        }
+                        GameState::Paused if ui.button("Resume").clicked() => {
+                            next_state.set(GameState::Playing);
+                        }
+                        _ => {}
+                    }
+                    ui.horizontal(|ui| {
+                        for speed in SPEEDS {
+                            let selected = time_controls.speed == speed;
+                            if ui.selectable_label(selected, format!("{speed}x")).clicked() {
+                                time_controls.speed = speed;
+                            }
+                        }
+                    });
                     if ui.button("Save Settings").clicked() {
                         save_events.send(SaveSettings);
                     }
@@ -64,6 +105,35 @@ impl GameUi {
                 ui.separator();
                 ui.radio_value(&mut settings.next_game.mode, GameMode::Survival, "Survival");
                 ui.radio_value(&mut settings.next_game.mode, GameMode::Match, "Match");
+                if settings.next_game.mode == GameMode::Match {
+                    ui.add(
+                        egui::Slider::new(&mut settings.next_game.rounds, 1..=9)
+                            .text("Rounds (Best Of)"),
+                    );
+                    ui.horizontal(|ui| {
+                        let mut has_limit = settings.next_game.time_limit_seconds.is_some();
+                        ui.checkbox(&mut has_limit, "Time Limit");
+                        let limit = &mut settings.next_game.time_limit_seconds;
+                        if has_limit {
+                            let seconds = limit.get_or_insert(180);
+                            ui.add(egui::Slider::new(seconds, 30..=900).text("Seconds"));
+                        } else {
+                            *limit = None;
+                        }
+                    });
+                }
+                ui.checkbox(
+                    &mut settings.next_game.catch_in,
+                    "Catches Free Jailed Teammates",
+                );
+                let difficulty = &mut settings.next_game.bot_difficulty;
+                egui::ComboBox::from_label("Bot Difficulty")
+                    .selected_text(difficulty.name())
+                    .show_ui(ui, |ui| {
+                        for option in BotDifficulty::ALL {
+                            ui.selectable_value(difficulty, option, option.name());
+                        }
+                    });
                 ui.add(
                     egui::Slider::new(&mut settings.next_game.players_per_squad, 1..=5000)
                         .text("Players Per Squad"),
@@ -73,6 +143,58 @@ impl GameUi {
                         .text("Squads Per Team"),
                 );
                 ui.add(egui::Slider::new(&mut settings.next_game.n_balls, 0..=2000).text("Balls"));
+                ui.add(
+                    egui::Slider::new(&mut settings.next_game.ticks_per_second, 10..=120)
+                        .text("Ticks Per Second"),
+                );
+                ui.horizontal(|ui| {
+                    ui.add_enabled(
+                        !settings.randomize_seed,
+                        egui::DragValue::new(&mut settings.next_game.seed),
+                    );
+                    ui.label("Seed");
+                    ui.checkbox(&mut settings.randomize_seed, "Randomize");
+                });
+                ui.label(format!("Current game seed: {}", config.seed));
+                ui.separator();
+
+                ui.collapsing("Stat Points", |ui| {
+                    point_buy_ui(ui, &mut settings.next_game);
+                });
+                ui.separator();
+
+                ui.collapsing("Replays", |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("Save Replay").clicked() {
+                            let replay = recorder.finish(&tick);
+                            let path = replay.default_path();
+                            match replay.save(&path) {
+                                Ok(()) => info!("Saved replay to {}", path.display()),
+                                Err(e) => error!("Failed to save replay: {e}"),
+                            }
+                            game_ui.replay_files = Replay::list_files();
+                        }
+                        if ui.button("Refresh").clicked() {
+                            game_ui.replay_files = Replay::list_files();
+                        }
+                    });
+                    let mut to_watch = None;
+                    for path in &game_ui.replay_files {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        if ui.button(format!("Watch {name}")).clicked() {
+                            to_watch = Some(path.clone());
+                        }
+                    }
+                    if let Some(path) = to_watch {
+                        match Replay::load(&path) {
+                            Ok(replay) => {
+                                game_ui.show = false;
+                                commands.add(WatchReplay(replay));
+                            }
+                            Err(e) => error!("Failed to load replay: {e}"),
+                        }
+                    }
+                });
                 ui.separator();
 
                 ui.collapsing("Controls", |ui| {
@@ -84,10 +206,38 @@ impl GameUi {
                             ui.label("toggle this window");
                             ui.end_row();
 
+                            ui.label("P");
+                            ui.label("pause");
+                            ui.end_row();
+
+                            ui.label("- / =");
+                            ui.label("slow down / speed up");
+                            ui.end_row();
+
+                            ui.label("0");
+                            ui.label("normal speed");
+                            ui.end_row();
+
                             ui.label("Left Click");
                             ui.label("select squad and place leader tokens");
                             ui.end_row();
 
+                            ui.label("Shift + Left Click");
+                            ui.label("add a waypoint to the squad's path");
+                            ui.end_row();
+
+                            ui.label("L");
+                            ui.label("toggle looping the squad's path");
+                            ui.end_row();
+
+                            ui.label("C");
+                            ui.label("toggle charging without waiting for stragglers");
+                            ui.end_row();
+
+                            ui.label("V");
+                            ui.label("throw a volley when auto throw is off");
+                            ui.end_row();
+
                             ui.label("Ctrl + Mouse");
                             ui.label("rotate camera");
                             ui.end_row();
@@ -139,3 +289,53 @@ impl GameUi {
         }
     }
 }
+
+/// Spends each team's budget. The bots only pick a preset.
+fn point_buy_ui(ui: &mut egui::Ui, config: &mut GameConfig) {
+    let team_squads = [config.team_size(0).squads, config.team_size(1).squads];
+    let point_buy = &mut config.point_buy;
+    ui.add(egui::Slider::new(&mut point_buy.budget, 0..=50).text("Budget Per Team"));
+
+    for (team, name) in [(0, "Your Team"), (1, "The Bad Guys")] {
+        ui.label(name);
+        let squads_per_team = team_squads[team as usize];
+        let spent = point_buy.spent(team, squads_per_team);
+        let mut remaining = point_buy.budget.saturating_sub(spent);
+        let team_points = &mut point_buy.teams[team as usize];
+        let preset_name = team_points.preset.map_or("Custom", StatPreset::name);
+        egui::ComboBox::from_id_source(("preset", team))
+            .selected_text(preset_name)
+            .show_ui(ui, |ui| {
+                if team == 0 {
+                    ui.selectable_value(&mut team_points.preset, None, "Custom");
+                }
+                for preset in StatPreset::ALL {
+                    ui.selectable_value(&mut team_points.preset, Some(preset), preset.name());
+                }
+            });
+        if team_points.preset.is_some() {
+            continue;
+        }
+        if team == 1 {
+            // Bots can't spend custom points.
+            team_points.preset = Some(default());
+            continue;
+        }
+
+        ui.label(format!("{remaining} points left"));
+        team_points
+            .squads
+            .resize(usize::from(squads_per_team), default());
+        for (i, points) in team_points.squads.iter_mut().enumerate() {
+            ui.collapsing(format!("Squad {}", i + 1), |ui| {
+                for (label, value) in points.fields_mut() {
+                    // Earlier sliders may have spent points this frame.
+                    let old = *value;
+                    let max = old + remaining;
+                    ui.add(egui::Slider::new(value, 0..=max).text(label));
+                    remaining = (remaining + old).saturating_sub(*value);
+                }
+            });
+        }
+    }
+}
@@ -16,6 +16,19 @@ impl<T: Clone> Grid2<T> {
         }
     }
 
+    pub fn shape(&self) -> UVec2 {
+        self.shape
+    }
+
+    /// All cells in row-major order.
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
     pub fn fill(&mut self, value: T) {
         self.cells.fill(value);
     }
@@ -1,6 +1,12 @@
 use crate::{
-    collision, geometry::Aabb2, occupancy_grid::OccupancyGrid, parameters::OCCUPANCY_CELL_SIZE,
+    ball_grid::BallGrid,
+    ball_paths::BallPaths,
+    collision,
+    geometry::Aabb2,
+    occupancy_grid::OccupancyGrid,
+    parameters::{BALL_GRID_CELL_SIZE, DODGE_CELL_SIZE, OCCUPANCY_CELL_SIZE},
     squad::SquadBehaviors,
+    visuals::{Visual, Visuals},
 };
 use bevy::prelude::*;
 use bevy_mod_picking::prelude::*;
@@ -25,15 +31,12 @@ impl Gym {
         let y_offset = -ht;
 
         // floor
-        commands.spawn((
+        let mut floor = commands.spawn((
             Floor,
             Gym,
-            PbrBundle {
-                mesh: assets.floor_mesh.clone(),
-                material: assets.floor_material.clone(),
-                transform: Transform::from_translation(Vec3::new(0.0, y_offset, 0.0)),
-                ..default()
-            },
+            TransformBundle::from_transform(Transform::from_translation(Vec3::new(
+                0.0, y_offset, 0.0,
+            ))),
             RigidBody::Fixed,
             Collider::cuboid(he.x, ht, he.z),
             CollisionGroups::new(
@@ -45,6 +48,9 @@ impl Gym {
             RapierPickable,
             On::<Pointer<Click>>::run(SquadBehaviors::set_leader_position),
         ));
+        if let Some(visual) = &assets.floor_visual {
+            floor.insert(visual.bundle());
+        }
 
         let walls = [
             (
@@ -88,6 +94,8 @@ pub struct GymParams {
     pub thickness: f32,
     pub player_spawn_width: f32,
     pub ball_spawn_width: f32,
+    /// Width of the sideline jails used by `GameConfig::catch_in`.
+    pub jail_width: f32,
 }
 
 impl Default for GymParams {
@@ -97,6 +105,7 @@ impl Default for GymParams {
             thickness: 1.0,
             player_spawn_width: 16.0,
             ball_spawn_width: 4.0,
+            jail_width: 4.0,
         }
     }
 }
@@ -130,41 +139,58 @@ impl GymParams {
         Aabb2::new([-he.x, -w].into(), [he.x, w].into())
     }
 
+    /// Each team's jail runs along one sideline of their half of the court.
+    pub fn jail_aabbs(&self) -> [Aabb2; 2] {
+        let he = self.half_extents();
+        let w = self.jail_width;
+        [
+            Aabb2::new([-he.x, 0.0].into(), [-he.x + w, he.z].into()),
+            Aabb2::new([he.x - w, -he.z].into(), [he.x, 0.0].into()),
+        ]
+    }
+
     pub fn occupancy_grid(&self) -> OccupancyGrid {
         assert_eq!(self.size.signum(), Vec3::ONE);
         OccupancyGrid::new(OCCUPANCY_CELL_SIZE, self.aabb2())
     }
+
+    pub fn ball_paths(&self) -> BallPaths {
+        BallPaths::new(DODGE_CELL_SIZE, self.aabb2())
+    }
+
+    pub fn ball_grid(&self) -> BallGrid {
+        BallGrid::new(BALL_GRID_CELL_SIZE, self.aabb2())
+    }
 }
 
 pub struct GymAssets {
     pub params: GymParams,
-    pub floor_mesh: Handle<Mesh>,
-    pub floor_material: Handle<StandardMaterial>,
+    /// `None` when running headless.
+    pub floor_visual: Option<Visual>,
 }
 
 impl GymAssets {
-    pub fn new(
-        params: GymParams,
-        meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<StandardMaterial>,
-    ) -> Self {
+    pub fn new(params: GymParams, visuals: Option<&mut Visuals>) -> Self {
         let he = params.half_extents();
         let ht = params.half_thickness();
         Self {
             params,
-            floor_mesh: meshes.add(
-                shape::Box {
-                    min_x: -he.x,
-                    max_x: he.x,
-                    min_y: -ht,
-                    max_y: ht,
-                    min_z: -he.z,
-                    max_z: he.z,
-                }
-                .try_into()
-                .unwrap(),
-            ),
-            floor_material: materials.add(Color::GRAY.into()),
+            floor_visual: visuals.map(|visuals| {
+                Visual::new(
+                    visuals.meshes.add(
+                        shape::Box {
+                            min_x: -he.x,
+                            max_x: he.x,
+                            min_y: -ht,
+                            max_y: ht,
+                            min_z: -he.z,
+                            max_z: he.z,
+                        }
+                        .into(),
+                    ),
+                    visuals.materials.add(Color::GRAY.into()),
+                )
+            }),
         }
     }
 }
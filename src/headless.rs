@@ -0,0 +1,215 @@
+use crate::{
+    controller::TeamControllers,
+    game_state::GameState,
+    match_progress::MatchProgress,
+    scoreboard::ScoreBoard,
+    settings::{GameConfig, GameSettings},
+    squad::{
+        PlayerStats, Squad, SquadAi, SquadBehaviors, SquadCommand, SquadCommands, SquadStates,
+    },
+    team::Team,
+    tick::{run_ticks, SimulationTick},
+    SimulationPlugin,
+};
+use bevy::prelude::*;
+use serde::Serialize;
+
+/// A match that runs without a window, as fast as the CPU allows.
+///
+/// Ticks are driven manually instead of by the clock, so the outcome only
+/// depends on the config, the stats given to each team and the orders given to
+/// the human or custom controlled teams. Only a single round is played.
+pub struct HeadlessMatch {
+    app: App,
+    team_stats: [PlayerStats; 2],
+    /// The team of each squad.
+    squad_teams: Vec<u8>,
+}
+
+impl HeadlessMatch {
+    /// Both teams are controlled by bots.
+    ///
+    /// `team_stats` replace any stats bought with `GameConfig::point_buy`, as
+    /// well as the bots' aim from `GameConfig::bot_difficulty`, but squad sizes
+    /// still come from the point buy.
+    pub fn new(config: GameConfig, team_stats: [PlayerStats; 2]) -> Self {
+        Self::with_controllers(config, team_stats, default())
+    }
+
+    /// The squads of a human team only move when given commands with
+    /// `queue_command`.
+    pub fn with_controllers(
+        config: GameConfig,
+        team_stats: [PlayerStats; 2],
+        controllers: TeamControllers,
+    ) -> Self {
+        let mut this = Self {
+            app: new_app(config, controllers),
+            team_stats,
+            squad_teams: Vec::new(),
+        };
+        this.setup_squads();
+        this
+    }
+
+    /// Starts over with a new config in a fresh `App`, so nothing carries over
+    /// from the previous match.
+    pub fn restart(&mut self, config: GameConfig) {
+        let controllers = self
+            .app
+            .world
+            .remove_resource::<TeamControllers>()
+            .unwrap_or_default();
+        self.app = new_app(config, controllers);
+        self.setup_squads();
+    }
+
+    fn setup_squads(&mut self) {
+        let world = &mut self.app.world;
+        let mut squad_teams = vec![0; world.resource::<SquadBehaviors>().squads.len()];
+        for (squad, team) in world
+            .query_filtered::<(&Squad, &Team), With<SquadAi>>()
+            .iter(world)
+        {
+            squad_teams[squad.squad as usize] = team.team();
+        }
+        let mut behaviors = world.resource_mut::<SquadBehaviors>();
+        for (behavior, &team) in behaviors.squads.iter_mut().zip(&squad_teams) {
+            behavior.stats = self.team_stats[team as usize];
+        }
+        self.squad_teams = squad_teams;
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    /// The team of each squad.
+    pub fn squad_teams(&self) -> &[u8] {
+        &self.squad_teams
+    }
+
+    /// The command is applied at the start of the next tick.
+    pub fn queue_command(&mut self, command: SquadCommand) {
+        self.app
+            .world
+            .resource_mut::<SquadCommands>()
+            .queue(command);
+    }
+
+    pub fn run_ticks(&mut self, n: u64) {
+        run_ticks(&mut self.app.world, n);
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.app.world.resource::<SimulationTick>().0
+    }
+
+    /// The number of players left on each team.
+    pub fn players_per_team(&self) -> [u32; 2] {
+        let states = self.app.world.resource::<SquadStates>();
+        let mut players = [0; 2];
+        for (state, &team) in states.squads.iter().zip(&self.squad_teams) {
+            players[team as usize] += state.num_players;
+        }
+        players
+    }
+
+    /// `Some(None)` means the match ended in a draw.
+    pub fn outcome(&self) -> Option<Option<u8>> {
+        let progress = self.app.world.resource::<MatchProgress>();
+        if progress.finished {
+            return Some(progress.winner());
+        }
+        match self.players_per_team() {
+            [0, 0] => Some(None),
+            [_, 0] => Some(Some(0)),
+            [0, _] => Some(Some(1)),
+            _ => None,
+        }
+    }
+
+    /// Players knocked out by each team's throws.
+    pub fn hits(&self) -> [u32; 2] {
+        let scores = &self.app.world.resource::<ScoreBoard>().team_scores;
+        [scores[0], scores[1]]
+    }
+
+    /// Throwers knocked out by each team's catches.
+    pub fn catches(&self) -> [u32; 2] {
+        let catches = &self.app.world.resource::<ScoreBoard>().team_catches;
+        [catches[0], catches[1]]
+    }
+
+    /// Players knocked out by each team, through hits or catches.
+    pub fn knockouts(&self) -> [u32; 2] {
+        let scoreboard = self.app.world.resource::<ScoreBoard>();
+        [scoreboard.knockouts(0), scoreboard.knockouts(1)]
+    }
+
+    /// Runs until one team is eliminated or `max_ticks` have passed.
+    pub fn play(mut self, max_ticks: u64) -> MatchReport {
+        let mut winner = None;
+        while self.tick() < max_ticks {
+            self.run_ticks(1);
+            if let Some(outcome) = self.outcome() {
+                winner = outcome;
+                break;
+            }
+        }
+        self.report(winner)
+    }
+
+    fn report(&self, winner: Option<u8>) -> MatchReport {
+        let ticks = self.tick();
+        let config = self.app.world.resource::<GameConfig>();
+        MatchReport {
+            winner,
+            ticks,
+            seconds: ticks as f32 * config.tick_seconds(),
+            hits: self.hits(),
+            catches: self.catches(),
+        }
+    }
+}
+
+fn new_app(mut config: GameConfig, controllers: TeamControllers) -> App {
+    config.rounds = 1;
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        SimulationPlugin,
+    ))
+    .insert_resource(controllers)
+    .insert_resource(GameSettings {
+        randomize_seed: false,
+        next_game: config,
+        ..default()
+    });
+    app.finish();
+    app.cleanup();
+
+    // Run the startup systems to spawn the game.
+    app.update();
+    GameState::transition_now(&mut app.world, GameState::Playing);
+    app
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MatchReport {
+    /// `None` for a draw or when time ran out.
+    pub winner: Option<u8>,
+    pub ticks: u64,
+    pub seconds: f32,
+    /// Players knocked out by each team's throws.
+    pub hits: [u32; 2],
+    /// Throwers knocked out by each team's catches.
+    pub catches: [u32; 2],
+}
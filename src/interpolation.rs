@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+
+/// Smooths the motion of an entity between fixed simulation ticks.
+///
+/// The simulation only sees the transforms from the end of each tick. Between
+/// ticks, the rendered transform is blended from the previous tick to the
+/// current one. Only use this on entities without a parent.
+#[derive(Component, Default)]
+pub struct TransformInterpolation {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+}
+
+impl TransformInterpolation {
+    /// Must run at the start of each tick, so the simulation never sees an
+    /// interpolated transform.
+    pub fn restore(mut entities: Query<(&Self, &mut Transform, &mut GlobalTransform)>) {
+        for (interp, mut tfm, mut global_tfm) in &mut entities {
+            if let Some(current) = interp.current {
+                *tfm = current;
+                *global_tfm = GlobalTransform::from(current);
+            }
+        }
+    }
+
+    /// Must run at the end of each tick.
+    pub fn record(mut entities: Query<(&mut Self, &Transform)>) {
+        for (mut interp, tfm) in &mut entities {
+            interp.previous = interp.current.or(Some(*tfm));
+            interp.current = Some(*tfm);
+        }
+    }
+
+    /// Must run in `PostUpdate` before transform propagation.
+    pub fn interpolate(fixed_time: Res<Time<Fixed>>, mut entities: Query<(&Self, &mut Transform)>) {
+        let s = fixed_time.overstep_percentage();
+        for (interp, mut tfm) in &mut entities {
+            let (Some(previous), Some(current)) = (interp.previous, interp.current) else {
+                continue;
+            };
+            tfm.translation = previous.translation.lerp(current.translation, s);
+            tfm.rotation = previous.rotation.slerp(current.rotation, s);
+        }
+    }
+}
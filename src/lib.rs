@@ -1,23 +1,42 @@
 mod ball;
+mod ball_grid;
+mod ball_paths;
 mod boundaries;
 mod collision;
+mod controller;
+mod environment;
+mod formation;
+mod game_state;
 mod game_ui;
 mod geometry;
 mod grid2;
 mod gym;
+mod headless;
+mod interpolation;
+mod match_progress;
+mod match_rng;
 mod occupancy_grid;
 mod opponent_ai;
 mod parameters;
 mod player;
+mod point_buy;
+mod replay;
 mod restart_game;
 mod scoreboard;
 mod settings;
 mod squad;
 mod squad_ui;
 mod team;
+mod tick;
+mod time_controls;
+mod visuals;
 
+use ball_grid::BallGrid;
+use ball_paths::BallPaths;
 use bevy::prelude::*;
 use bevy::render::view::NoFrustumCulling;
+use bevy::transform::systems::{propagate_transforms, sync_simple_transforms};
+use bevy::transform::TransformSystem;
 use bevy::window::CursorGrabMode;
 use bevy_egui::egui;
 use bevy_egui::egui::Color32;
@@ -29,38 +48,49 @@ use bevy_rapier3d::prelude::*;
 use collision::{handle_ball_floor_collisions, handle_ball_player_collisions};
 use game_ui::GameUi;
 use gym::GymParams;
+use interpolation::TransformInterpolation;
+use match_progress::MatchProgress;
 use occupancy_grid::OccupancyGrid;
 use opponent_ai::control_bot_team;
-use player::{AvoidPlayers, KnockedOut, Player};
+use player::{AvoidPlayers, Jail, KnockedOut, Player};
+use replay::Playback;
 use restart_game::start_game;
 use scoreboard::ScoreBoard;
-use settings::{GameSettings, SaveSettings};
+use settings::SaveSettings;
 use smooth_bevy_cameras::controllers::orbit::OrbitCameraPlugin;
 use smooth_bevy_cameras::LookTransformPlugin;
-use squad::{SquadAi, SquadStates};
+use squad::{SquadAi, SquadBehaviors, SquadCommands, SquadStates};
 use squad_ui::SquadUi;
+use tick::SimulationTick;
+use time_controls::TimeControls;
+
+pub use controller::{ControllerKind, SquadController, TeamController, TeamControllers};
+pub use environment::{
+    Environment, EnvironmentConfig, Observation, SquadAction, SquadObservation, Step,
+};
+pub use game_state::GameState;
+pub use headless::{HeadlessMatch, MatchReport};
+pub use opponent_ai::{BotDifficulty, BotParams};
+pub use point_buy::{PointBuy, StatPoints, StatPreset, TeamPoints};
+pub use settings::{GameConfig, GameMode, GameSettings, TeamSize};
+pub use squad::{PlayerStats, SquadCommand, SquadKnobs};
 
 // IDEAS
-// - multi-step squad paths
 // - neutral zones; don't let players into other team's spawn zone
-// - add point-buy for squad statistics
-// - make the throw loft adjustable, using a gizmo to show the arc
-// - make players holding balls run to the "front" of their cluster
 // - let players holding balls run closer to the current target enemy if they're
 //   out of range
-// - let players leave their cluster to quickly grab a nearby ball
-// - let players block incoming thrown balls if they are holding a ball
-// - make the cluster shape adjustable
-// - let players dodge out of the way of thrown balls
 
+/// The full game: simulation, rendering and UI.
+///
+/// Requires `DefaultPlugins`.
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
+            SimulationPlugin,
             EguiPlugin,
             DefaultPickingPlugins,
-            RapierPhysicsPlugin::<NoUserData>::default(),
             // RapierDebugRenderPlugin::default(),
             LookTransformPlugin,
             OrbitCameraPlugin::default(),
@@ -71,56 +101,196 @@ impl Plugin for GamePlugin {
         .insert_resource(RapierBackendSettings {
             require_markers: true,
         })
-        .init_resource::<GameSettings>()
+        .insert_resource(TeamControllers::with_human(0))
         .init_resource::<GameUi>()
-        .init_resource::<GymParams>()
+        .init_resource::<TimeControls>()
         .init_resource::<SquadUi>()
         .add_systems(
             Startup,
             (
-                GameSettings::load,
-                start_game.after(GameSettings::load),
+                GameSettings::load.before(start_game),
                 transparency_hack,
                 configure_egui_visuals,
             ),
         )
-        .add_systems(PreUpdate, emulate_right_click_with_alt)
-        // Needs to be PreUpdate so AI can use correct SquadState values
-        .add_systems(PreUpdate, SquadStates::update)
+        .add_systems(OnEnter(GameState::MainMenu), GameState::enter_main_menu)
+        .add_systems(OnExit(GameState::MainMenu), GameState::exit_main_menu)
+        .add_systems(OnEnter(GameState::GameOver), GameState::enter_game_over)
+        .add_systems(
+            PreUpdate,
+            (emulate_right_click_with_alt, TimeControls::apply_speed),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                TransformInterpolation::restore.before(SimulationSet::Squads),
+                TransformInterpolation::record.after(SimulationSet::Physics),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            TransformInterpolation::interpolate.before(TransformSystem::TransformPropagate),
+        )
         .add_systems(
             Update,
             (
                 grab_mouse,
                 GameUi::update,
-                GameUi::toggle,
+                GameUi::toggle.run_if(not(in_state(GameState::MainMenu))),
+                GameState::toggle_pause,
+                TimeControls::change_speed,
+                TimeControls::draw,
                 ScoreBoard::draw,
                 SquadUi::toggle,
+                SquadBehaviors::toggle_patrol,
+                SquadBehaviors::toggle_charge,
+                SquadBehaviors::volley,
                 SquadUi::draw,
-                OccupancyGrid::update,
-                SquadAi::move_to_requested_positions,
-                SquadAi::find_target_enemy,
-                control_bot_team,
+                SquadUi::edit_behavior,
+                Playback::draw.run_if(resource_exists::<Playback>()),
+                MatchProgress::draw_results.run_if(in_state(GameState::GameOver)),
             ),
         )
-        .add_systems(Update, Player::initialize_kinematics)
-        .add_systems(
-            Update,
-            (
-                AvoidPlayers::avoid_other_players,
-                Player::throw_ball_at_enemy,
-                Player::follow_leader,
-                KnockedOut::update,
-                handle_ball_player_collisions,
-                handle_ball_floor_collisions,
-            )
-                .after(Player::initialize_kinematics)
-                .before(Player::finalize_kinematics),
-        )
-        .add_systems(Update, Player::finalize_kinematics)
         .add_systems(Last, GameSettings::save_on_exit_or_request);
     }
 }
 
+/// Only the gameplay systems and physics, without any rendering or UI.
+///
+/// This can run under `MinimalPlugins` (plus `TransformPlugin` and
+/// `HierarchyPlugin`) without a window, in which case entities are spawned
+/// without meshes or materials. Unless a `TeamControllers` resource is inserted,
+/// all teams are controlled by bots.
+///
+/// The simulation runs in `FixedUpdate` at `GameConfig::ticks_per_second`, so
+/// match results don't depend on the frame rate. It only runs in
+/// `GameState::Playing`, while the app starts in `GameState::MainMenu`.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        type Physics = RapierPhysicsPlugin<NoUserData>;
+
+        GameState::add_to(app);
+
+        app.add_plugins(Physics::default().with_default_system_setup(false))
+            .init_resource::<GameSettings>()
+            .init_resource::<GymParams>()
+            .init_resource::<TeamControllers>()
+            .init_resource::<SquadCommands>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<MatchProgress>()
+            // Rapier's async collider systems need these, even though we never
+            // use async colliders. They already exist with `DefaultPlugins`.
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<SceneSpawner>()
+            .add_systems(Startup, start_game)
+            .configure_sets(
+                FixedUpdate,
+                (
+                    SimulationSet::Squads,
+                    SimulationSet::Players,
+                    SimulationSet::Physics,
+                    SimulationSet::Rules,
+                )
+                    .chain(),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    SimulationSet::Squads.run_if(GameState::is_simulating),
+                    SimulationSet::Players.run_if(GameState::is_simulating),
+                    SimulationSet::Physics.run_if(GameState::is_simulating),
+                    SimulationSet::Rules.run_if(GameState::is_simulating),
+                ),
+            )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    PhysicsSet::SyncBackend,
+                    PhysicsSet::StepSimulation,
+                    PhysicsSet::Writeback,
+                    SimulationSet::PropagateTransforms,
+                )
+                    .chain()
+                    .in_set(SimulationSet::Physics),
+            )
+            .add_systems(
+                FixedUpdate,
+                // Everything in a tick is chained so that matches are
+                // deterministic.
+                (
+                    SquadCommands::apply,
+                    SquadStates::update,
+                    // The AI needs up-to-date SquadState values.
+                    apply_deferred,
+                    OccupancyGrid::update,
+                    BallPaths::update,
+                    BallGrid::update,
+                    SquadAi::find_target_enemy,
+                    control_bot_team,
+                    TeamControllers::run_custom,
+                    SquadBehaviors::advance_waypoints,
+                    SquadAi::move_to_requested_positions,
+                )
+                    .chain()
+                    .in_set(SimulationSet::Squads),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    Player::initialize_kinematics,
+                    AvoidPlayers::avoid_other_players,
+                    Player::throw_ball_at_enemy,
+                    Player::chase_balls,
+                    Player::follow_leader,
+                    Player::dodge_balls,
+                    KnockedOut::update,
+                    handle_ball_player_collisions,
+                    Jail::free_players.run_if(resource_exists::<Jail>()),
+                    handle_ball_floor_collisions,
+                    Player::finalize_kinematics,
+                )
+                    .chain()
+                    .in_set(SimulationSet::Players),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    Physics::get_systems(PhysicsSet::SyncBackend).in_set(PhysicsSet::SyncBackend),
+                    Physics::get_systems(PhysicsSet::StepSimulation)
+                        .in_set(PhysicsSet::StepSimulation),
+                    Physics::get_systems(PhysicsSet::Writeback).in_set(PhysicsSet::Writeback),
+                    // Keep GlobalTransform in sync with the physics results, even
+                    // when multiple ticks run in a single frame.
+                    (sync_simple_transforms, propagate_transforms)
+                        .in_set(SimulationSet::PropagateTransforms),
+                    SimulationTick::advance
+                        .after(SimulationSet::PropagateTransforms)
+                        .in_set(SimulationSet::Physics),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                MatchProgress::check_round_end.in_set(SimulationSet::Rules),
+            );
+    }
+}
+
+/// The phases of a simulation tick, which all run in `FixedUpdate`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SystemSet)]
+pub enum SimulationSet {
+    /// Squad accounting and squad-level AI.
+    Squads,
+    /// Player movement, throwing and ball handling.
+    Players,
+    /// The Rapier step.
+    Physics,
+    PropagateTransforms,
+    /// Win conditions.
+    Rules,
+}
+
 // Mostly for laptops without good right click and drag support.
 fn emulate_right_click_with_alt(keys: Res<Input<KeyCode>>, mut mouse: ResMut<Input<MouseButton>>) {
     if keys.pressed(KeyCode::AltLeft) || keys.pressed(KeyCode::AltRight) {
@@ -173,8 +343,7 @@ fn transparency_hack(
                     size: 0.0,
                     subdivisions: 0,
                 }
-                .try_into()
-                .unwrap(),
+                .into(),
             ),
             ..default()
         })
@@ -0,0 +1,221 @@
+use crate::{
+    game_state::GameState,
+    game_ui::GameUi,
+    player::{KnockedOut, Player},
+    restart_game::{ConfigOverride, RestartGame, StartNextRound},
+    scoreboard::ScoreBoard,
+    settings::{GameConfig, GameMode, GameSettings},
+    team::Team,
+    tick::SimulationTick,
+};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::cmp::Ordering;
+
+const TEAM_NAMES: [&str; 2] = ["Green", "Blue"];
+
+/// The rounds of the current `GameMode::Match`.
+///
+/// Survives the restarts between rounds, but not `RestartGame`.
+#[derive(Default, Resource)]
+pub struct MatchProgress {
+    pub rounds: Vec<RoundResult>,
+    /// No more rounds will be played. The simulation stops.
+    pub finished: bool,
+    /// The config of the first round, before the seed changes between rounds.
+    pub first_round: Option<GameConfig>,
+}
+
+#[derive(Clone, Copy)]
+pub struct RoundResult {
+    /// `None` for a draw.
+    pub winner: Option<u8>,
+    pub end: RoundEnd,
+    pub seconds: f32,
+    /// Players knocked out by each team's throws.
+    pub hits: [u32; 2],
+    /// Throwers knocked out by each team's catches.
+    pub catches: [u32; 2],
+    pub players_left: [u32; 2],
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum RoundEnd {
+    Elimination,
+    TimeLimit,
+}
+
+impl MatchProgress {
+    pub fn round_wins(&self) -> [u32; 2] {
+        let mut wins = [0; 2];
+        for winner in self.rounds.iter().filter_map(|r| r.winner) {
+            wins[winner as usize] += 1;
+        }
+        wins
+    }
+
+    /// The team with the most round wins, or `None` for a draw.
+    pub fn winner(&self) -> Option<u8> {
+        leader(self.round_wins())
+    }
+
+    /// Must run at the end of each tick.
+    pub fn check_round_end(
+        mut commands: Commands,
+        config: Res<GameConfig>,
+        tick: Res<SimulationTick>,
+        scoreboard: Res<ScoreBoard>,
+        mut progress: ResMut<Self>,
+        mut next_state: ResMut<NextState<GameState>>,
+        players: Query<&Team, (With<Player>, Without<KnockedOut>)>,
+    ) {
+        if config.mode != GameMode::Match {
+            return;
+        }
+
+        let mut players_left = [0; 2];
+        for team in &players {
+            players_left[team.team() as usize] += 1;
+        }
+        let hits = [scoreboard.team_scores[0], scoreboard.team_scores[1]];
+        let catches = [scoreboard.team_catches[0], scoreboard.team_catches[1]];
+        let seconds = tick.0 as f32 * config.tick_seconds();
+
+        let (winner, end) = match players_left {
+            [0, 0] => (None, RoundEnd::Elimination),
+            [_, 0] => (Some(0), RoundEnd::Elimination),
+            [0, _] => (Some(1), RoundEnd::Elimination),
+            _ => {
+                let Some(limit) = config.time_limit_seconds else {
+                    return;
+                };
+                if seconds < limit as f32 {
+                    return;
+                }
+                // Tiebreak by knockouts.
+                let knockouts = [scoreboard.knockouts(0), scoreboard.knockouts(1)];
+                (leader(knockouts), RoundEnd::TimeLimit)
+            }
+        };
+
+        progress.rounds.push(RoundResult {
+            winner,
+            end,
+            seconds,
+            hits,
+            catches,
+            players_left,
+        });
+
+        let wins = progress.round_wins();
+        let to_win = config.rounds_to_win();
+        if wins.iter().any(|&w| w >= to_win) || progress.rounds.len() as u32 >= config.rounds {
+            progress.finished = true;
+            next_state.set(GameState::GameOver);
+        } else {
+            commands.add(StartNextRound);
+        }
+    }
+
+    pub fn draw_results(
+        mut commands: Commands,
+        progress: Res<Self>,
+        config: Res<GameConfig>,
+        settings: Res<GameSettings>,
+        game_ui: Res<GameUi>,
+        mut next_state: ResMut<NextState<GameState>>,
+        mut contexts: EguiContexts,
+    ) {
+        if game_ui.show {
+            return;
+        }
+
+        egui::Window::new("Results")
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.vertical_centered(|ui| {
+                    let wins = progress.round_wins();
+                    match progress.winner() {
+                        Some(team) => ui.heading(format!("{} wins!", TEAM_NAMES[team as usize])),
+                        None => ui.heading("Draw!"),
+                    };
+                    ui.label(format!("{} - {}", wins[0], wins[1]));
+                });
+                ui.separator();
+
+                egui::Grid::new("results").striped(true).show(ui, |ui| {
+                    ui.label("Round");
+                    ui.label("Winner");
+                    ui.label("Time");
+                    for name in TEAM_NAMES {
+                        ui.label(format!("{name} Hits"));
+                        ui.label(format!("{name} Catches"));
+                        ui.label(format!("{name} Left"));
+                    }
+                    ui.end_row();
+
+                    let mut total_hits = [0; 2];
+                    let mut total_catches = [0; 2];
+                    for (i, round) in progress.rounds.iter().enumerate() {
+                        ui.label(format!("{}", i + 1));
+                        let winner = round.winner.map_or("Draw", |t| TEAM_NAMES[t as usize]);
+                        match round.end {
+                            RoundEnd::Elimination => ui.label(winner),
+                            RoundEnd::TimeLimit => ui.label(format!("{winner} (time)")),
+                        };
+                        ui.label(format!("{:.0}s", round.seconds));
+                        for t in 0..2 {
+                            ui.label(format!("{}", round.hits[t]));
+                            ui.label(format!("{}", round.catches[t]));
+                            ui.label(format!("{}", round.players_left[t]));
+                            total_hits[t] += round.hits[t];
+                            total_catches[t] += round.catches[t];
+                        }
+                        ui.end_row();
+                    }
+
+                    ui.label("Total");
+                    ui.label("");
+                    ui.label(format!(
+                        "{:.0}s",
+                        progress.rounds.iter().map(|r| r.seconds).sum::<f32>()
+                    ));
+                    for (hits, catches) in total_hits.into_iter().zip(total_catches) {
+                        ui.label(format!("{hits}"));
+                        ui.label(format!("{catches}"));
+                        ui.label("");
+                    }
+                    ui.end_row();
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Rematch").clicked() {
+                        let mut rematch = progress
+                            .first_round
+                            .clone()
+                            .unwrap_or_else(|| config.clone());
+                        if settings.randomize_seed {
+                            rematch.seed = rand::random();
+                        }
+                        commands.insert_resource(ConfigOverride(rematch));
+                        commands.add(RestartGame);
+                    }
+                    if ui.button("Main Menu").clicked() {
+                        next_state.set(GameState::MainMenu);
+                    }
+                });
+            });
+    }
+}
+
+fn leader([a, b]: [u32; 2]) -> Option<u8> {
+    match a.cmp(&b) {
+        Ordering::Greater => Some(0),
+        Ordering::Less => Some(1),
+        Ordering::Equal => None,
+    }
+}
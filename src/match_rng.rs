@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The source of all randomness in a match.
+///
+/// It's seeded from `GameConfig::seed`, so the same seed and inputs will replay
+/// the same match. Systems that use it must run in a fixed order relative to
+/// each other, otherwise the sequence of random numbers they see can change
+/// between runs.
+///
+/// Unlike `StdRng`, the algorithm is guaranteed not to change between `rand`
+/// releases, which would break saved seeds and replays.
+#[derive(Resource)]
+pub struct MatchRng(ChaCha8Rng);
+
+impl MatchRng {
+    pub fn new(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl RngCore for MatchRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
@@ -1,10 +1,14 @@
 use crate::{
+    ball_grid::BallGrid,
     boundaries::Boundaries,
-    geometry::{Circle, Ray2},
+    formation::{facing_along, Formation},
+    geometry::{Aabb2, Circle, Ray2},
     gym::GymParams,
+    match_rng::MatchRng,
     settings::{GameConfig, GameMode},
     squad::{AllSquadAssets, Squad, SquadAi, SquadBehaviors, SquadState, SquadStates},
     team::{AllTeamAssets, Team},
+    tick::SimulationTick,
 };
 use bevy::{
     ecs::system::{Command, RunSystemOnce},
@@ -12,21 +16,73 @@ use bevy::{
 };
 use rand::Rng;
 
-#[derive(Component)]
-pub struct Bot;
+mod difficulty;
+
+pub use difficulty::{BotDifficulty, BotParams};
+
+/// Marks squads controlled by the heuristic bot.
+#[derive(Component, Default)]
+pub struct Bot {
+    scary: Noticed,
+    vulnerable: Noticed,
+}
+
+/// The enemy squad that a bot has its eye on, and since when.
+#[derive(Default)]
+struct Noticed {
+    squad: Option<u8>,
+    since_tick: u64,
+}
+
+impl Noticed {
+    /// True once `squad` has been the one noticed for `delay` ticks.
+    fn react(&mut self, squad: Option<u8>, tick: u64, delay: u64) -> bool {
+        if squad != self.squad {
+            self.squad = squad;
+            self.since_tick = tick;
+        }
+        tick >= self.since_tick + delay
+    }
+}
+
+/// Targets further than this many throw distances away are flanked.
+const FLANK_DISTANCE: f32 = 2.5;
+/// Squads with fewer balls than this go looking for more instead of attacking.
+const BALL_HUNGRY_PERCENT: u32 = 50;
+/// Distance between the candidate spots that squads consider collecting balls
+/// from.
+const BALL_SEARCH_SPACING: f32 = 8.0;
+/// Balls this far away are worth half as much as balls right here.
+const BALL_SEARCH_DISTANCE_SCALE: f32 = 30.0;
+/// For this long after a match starts, squads race for the balls in the
+/// middle of the court.
+const CENTER_RUSH_SECONDS: f32 = 20.0;
+const CENTER_RUSH_BONUS: f32 = 2.0;
 
 /// Sets leader tokens for squads to follow.
 #[allow(clippy::complexity)]
 pub fn control_bot_team(
     mut commands: Commands,
     config: Res<GameConfig>,
+    gym_params: Res<GymParams>,
+    tick: Res<SimulationTick>,
     bounds: Res<Boundaries>,
+    ball_grid: Res<BallGrid>,
+    mut rng: ResMut<MatchRng>,
     mut behaviors: ResMut<SquadBehaviors>,
     states: Res<SquadStates>,
-    bot_squad_ais: Query<(&Team, &Squad, &GlobalTransform), (With<Bot>, With<SquadAi>)>,
+    mut bot_squad_ais: Query<(&Team, &Squad, &GlobalTransform, &mut Bot), With<SquadAi>>,
     all_squad_ais: Query<(&Team, &Squad), With<SquadAi>>,
 ) {
-    for (team, squad, tfm) in &bot_squad_ais {
+    let params = config.bot_difficulty.params();
+    let reaction_ticks = params.reaction_ticks(&config);
+    let decision_ticks = params.decision_ticks(&config);
+    let seconds = tick.0 as f32 * config.tick_seconds();
+    let center_rush = (seconds < CENTER_RUSH_SECONDS)
+        .then(|| gym_params.ball_spawn_aabb())
+        .filter(|&center| ball_grid.count_in_aabb(center) > 0);
+
+    for (team, squad, tfm, mut bot) in &mut bot_squad_ais {
         let state = &states.squads[squad.squad as usize];
         if state.num_players == 0 {
             if config.mode == GameMode::Survival {
@@ -35,15 +91,49 @@ pub fn control_bot_team(
             continue;
         }
 
-        let pos = state.center_of_mass;
+        // Squads take turns deciding.
+        if (tick.0 + u64::from(squad.squad)) % decision_ticks != 0 {
+            continue;
+        }
 
-        let threats =
-            ThreatLevels::assess(&behaviors, &states, &all_squad_ais, team, squad, state, tfm);
+        let pos = state.center_of_mass;
+        let hungry = center_rush.is_some() || state.ball_percent() < BALL_HUNGRY_PERCENT;
+
+        let mut threats = ThreatLevels::assess(
+            &params,
+            &behaviors,
+            &states,
+            &all_squad_ais,
+            team,
+            squad,
+            state,
+            tfm,
+        );
+        // Nobody reacts instantly.
+        if !bot
+            .scary
+            .react(threats.scary.map(|(s, _)| s), tick.0, reaction_ticks)
+        {
+            threats.scary = None;
+        }
+        if !bot
+            .vulnerable
+            .react(threats.vulnerable.map(|(s, _)| s), tick.0, reaction_ticks)
+        {
+            threats.vulnerable = None;
+        }
 
         let mut safe = true;
+        // Only run away at full speed.
+        behaviors.squads[squad.squad as usize].charge = threats.scary.is_some();
         if let Some((scary, scary_pos)) = threats.scary {
+            // A column is narrow, so it's easier to squeeze past the enemy.
+            let behavior = &mut behaviors.squads[squad.squad as usize];
+            behavior.formation = Formation::Column;
+            behavior.facing = facing_along((pos - scary_pos).xz());
             let enemy_state = &states.squads[scary as usize];
             safe = run_from_enemy(
+                &params,
                 &bounds,
                 &mut behaviors,
                 squad,
@@ -54,33 +144,186 @@ pub fn control_bot_team(
                 scary_pos,
             );
         } else {
-            // We aren't scared. Let's go on the attack.
-            if let Some((_target_squad, target_squad_pos)) = threats.vulnerable {
+            // We aren't scared. Let's go on the attack, as long as we have
+            // balls to throw.
+            if let Some((target_squad, target_squad_pos)) = threats.vulnerable.filter(|_| !hungry) {
+                let target_radius = states.squads[target_squad as usize].cluster_radius;
                 let behavior = &mut behaviors.squads[squad.squad as usize];
+                // A line puts every thrower in range at once.
+                behavior.formation = Formation::Line;
+                behavior.facing = facing_along((target_squad_pos - pos).xz());
                 let target_dist = target_squad_pos.distance(pos);
                 let throw_dist = (behavior.stats.throw_distance - state.cluster_radius).max(0.0);
-                let ratio = throw_dist / target_dist;
-                let throw_pos = ratio * pos + (1.0 - ratio) * target_squad_pos;
-                behavior.leader_position = Some(throw_pos.xz());
+                if !behavior.waypoints.is_empty() {
+                    // Stick to the plan.
+                } else if throw_dist > 0.0 && target_dist > FLANK_DISTANCE * throw_dist {
+                    let [swing, attack] = plan_flank(
+                        &bounds,
+                        pos.xz(),
+                        target_squad_pos.xz(),
+                        target_radius,
+                        throw_dist,
+                    );
+                    behavior.go_to(swing);
+                    behavior.add_waypoint(attack);
+                } else {
+                    let ratio = throw_dist / target_dist;
+                    let throw_pos = ratio * pos + (1.0 - ratio) * target_squad_pos;
+                    behavior.go_to(throw_pos.xz());
+                }
             }
         }
 
         if safe {
-            // TODO: actual queries for balls
-            let behavior = &mut behaviors.squads[squad.squad as usize];
+            let behavior = &behaviors.squads[squad.squad as usize];
             let dist_from_leader_pos = behavior
                 .leader_position
                 .map(|leader_pos| leader_pos.distance(pos.xz()))
                 .unwrap_or_default();
-            if dist_from_leader_pos < 5.0 {
+            let arrived = dist_from_leader_pos < params.replan_distance;
+            let search_radius = ball_search_radius(state);
+
+            if hungry && behavior.waypoints.is_empty() {
+                // Keep going unless we got there or the balls there are gone.
+                let stale = behavior.leader_position.is_none_or(|leader_pos| {
+                    arrived
+                        || ball_grid.count_in_circle(Circle::new(leader_pos, search_radius)) == 0
+                });
+                if !stale {
+                    continue;
+                }
+
+                let teammate_targets: Vec<_> = all_squad_ais
+                    .iter()
+                    .filter(|(t, s)| t.team() == team.team() && s.squad != squad.squad)
+                    .filter_map(|(_, s)| behaviors.squads[s.squad as usize].leader_position)
+                    .collect();
+                let enemies: Vec<_> = all_squad_ais
+                    .iter()
+                    .filter(|(t, _)| t.team() != team.team())
+                    .map(|(_, s)| {
+                        let enemy_state = &states.squads[s.squad as usize];
+                        let enemy_behavior = &behaviors.squads[s.squad as usize];
+                        EnemyThreat::new(enemy_state, enemy_behavior.stats.throw_distance)
+                    })
+                    .collect();
+                if let Some(target) = find_balls(
+                    &bounds,
+                    &ball_grid,
+                    center_rush,
+                    state,
+                    &enemies,
+                    &teammate_targets,
+                    params.spread_out,
+                ) {
+                    // Spread out to pick up as many as possible.
+                    let behavior = &mut behaviors.squads[squad.squad as usize];
+                    behavior.formation = Formation::Disc;
+                    behavior.go_to(target);
+                    continue;
+                }
+            }
+
+            let behavior = &mut behaviors.squads[squad.squad as usize];
+            if arrived && behavior.waypoints.is_empty() {
                 // Choose a new position.
-                let mut rng = rand::thread_rng();
                 let ball_x = rng.gen_range(bounds.min.x..bounds.max.x);
                 let ball_y = rng.gen_range(bounds.min.y..bounds.max.y);
-                behavior.leader_position = Some(Vec2::new(ball_x, ball_y));
+                behavior.go_to(Vec2::new(ball_x, ball_y));
+            }
+        }
+    }
+}
+
+/// An enemy squad as a danger to squads collecting balls.
+struct EnemyThreat {
+    position: Vec2,
+    /// How close we can get before the enemy can hit us.
+    danger_radius: f32,
+    /// The fraction of enemy players holding a ball.
+    armed: f32,
+}
+
+impl EnemyThreat {
+    fn new(state: &SquadState, throw_distance: f32) -> Self {
+        Self {
+            position: state.center_of_mass.xz(),
+            danger_radius: state.cluster_radius + throw_distance,
+            armed: state.ball_percent() as f32 / 100.0,
+        }
+    }
+
+    /// From 0 when we're out of range or they have no balls, to 1 when we're
+    /// right on top of a fully armed enemy.
+    fn danger_at(&self, position: Vec2) -> f32 {
+        let closeness = (1.0 - position.distance(self.position) / self.danger_radius).max(0.0);
+        self.armed * closeness
+    }
+}
+
+/// Ground balls within this distance of a spot count as being there.
+fn ball_search_radius(state: &SquadState) -> f32 {
+    state.cluster_radius.max(0.75 * BALL_SEARCH_SPACING)
+}
+
+/// Picks the best spot on the court to collect balls from, weighing how many
+/// balls are there against how far away and how dangerous it is.
+///
+/// Returns the middle of the balls nearest that spot.
+fn find_balls(
+    bounds: &Boundaries,
+    ball_grid: &BallGrid,
+    center_rush: Option<Aabb2>,
+    state: &SquadState,
+    enemies: &[EnemyThreat],
+    teammate_targets: &[Vec2],
+    spread_out: f32,
+) -> Option<Vec2> {
+    let aabb = bounds.aabb2();
+    let pos = state.center_of_mass.xz();
+    let radius = ball_search_radius(state);
+
+    let shape = (aabb.shape() / BALL_SEARCH_SPACING).ceil().as_uvec2();
+    let mut best = None;
+    let mut best_score = 0.0;
+    for y in 0..shape.y {
+        for x in 0..shape.x {
+            let spot = aabb.min + BALL_SEARCH_SPACING * (Vec2::new(x as f32, y as f32) + 0.5);
+            let density = ball_grid.density(Circle::new(spot, radius));
+            if density == 0.0 {
+                continue;
+            }
+
+            let mut score = density / (1.0 + spot.distance(pos) / BALL_SEARCH_DISTANCE_SCALE);
+            for enemy in enemies {
+                score *= 1.0 - enemy.danger_at(spot);
+            }
+            if teammate_targets.iter().any(|t| t.distance(spot) < radius) {
+                // Leave it for the teammate who's already going there.
+                score *= 1.0 - spread_out;
+            }
+            if center_rush.is_some_and(|center| {
+                spot.cmpge(center.min - radius).all() && spot.cmple(center.max + radius).all()
+            }) {
+                score *= CENTER_RUSH_BONUS;
+            }
+
+            if score > best_score {
+                best = Some(spot);
+                best_score = score;
             }
         }
     }
+
+    // Aim for the balls our empty-handed players can actually pick up.
+    let spot = best?;
+    let wanted = (state.num_players - state.num_holding_balls).max(1) as usize;
+    let balls = ball_grid.nearest(spot, wanted, radius);
+    if balls.is_empty() {
+        return Some(spot);
+    }
+    let sum: Vec2 = balls.iter().map(|b| b.position).sum();
+    Some(sum / balls.len() as f32)
 }
 
 #[derive(Default)]
@@ -92,7 +335,9 @@ struct ThreatLevels {
 }
 
 impl ThreatLevels {
+    #[allow(clippy::too_many_arguments)]
     fn assess(
+        params: &BotParams,
         behaviors: &SquadBehaviors,
         states: &SquadStates,
         all_squad_ais: &Query<(&Team, &Squad), With<SquadAi>>,
@@ -101,7 +346,13 @@ impl ThreatLevels {
         state: &SquadState,
         tfm: &GlobalTransform,
     ) -> Self {
-        let max_ball_ratio = 2.0;
+        let BotParams {
+            max_ball_ratio,
+            ball_factor,
+            dist_factor,
+            gang_up,
+            ..
+        } = *params;
 
         let mut threat_levels = Self::default();
         let mut max_scary = 0.0;
@@ -124,9 +375,6 @@ impl ThreatLevels {
 
             let enemy_dist = enemy_state.center_of_mass.distance(tfm.translation());
 
-            let ball_factor = 0.5;
-            let dist_factor = 0.5;
-
             if ball_ratio >= 1.0 {
                 // We're not scared of them.
 
@@ -137,7 +385,22 @@ impl ThreatLevels {
                 let throw_dist = behavior.stats.throw_distance;
                 let dist_ratio = throw_dist / enemy_dist.max(0.001);
 
-                let vuln_metric = ball_factor * ball_ratio + dist_factor * dist_ratio;
+                let mut vuln_metric = ball_factor * ball_ratio + dist_factor * dist_ratio;
+                // Gang up on enemies that a teammate can already hit.
+                let teammate_in_range = all_squad_ais.iter().any(|(mate_team, mate_squad)| {
+                    let mate_state = &states.squads[mate_squad.squad as usize];
+                    let mate_behavior = &behaviors.squads[mate_squad.squad as usize];
+                    mate_team.team() == team.team()
+                        && mate_squad.squad != squad.squad
+                        && mate_state.num_players > 0
+                        && mate_state
+                            .center_of_mass
+                            .distance(enemy_state.center_of_mass)
+                            < mate_state.cluster_radius + mate_behavior.stats.throw_distance
+                });
+                if teammate_in_range {
+                    vuln_metric *= 1.0 + gang_up;
+                }
                 if vuln_metric > max_vuln {
                     threat_levels.vulnerable =
                         Some((enemy_squad.squad, enemy_state.center_of_mass));
@@ -168,6 +431,7 @@ impl ThreatLevels {
 /// Returns true iff the squad is at a safe distance from any threats.
 #[allow(clippy::too_many_arguments)]
 fn run_from_enemy(
+    params: &BotParams,
     bounds: &Boundaries,
     behaviors: &mut SquadBehaviors,
     squad: &Squad,
@@ -178,8 +442,8 @@ fn run_from_enemy(
     enemy_pos: Vec3,
 ) -> bool {
     let enemy_behavior = &behaviors.squads[enemy_squad as usize];
-    let safety_margin = 2.0;
-    let safe_dist = safety_margin * (state.cluster_radius + enemy_behavior.stats.throw_distance);
+    let safe_dist =
+        params.safety_margin * (state.cluster_radius + enemy_behavior.stats.throw_distance);
     let current_dist = pos.distance(enemy_pos);
     if current_dist > safe_dist {
         return true;
@@ -240,12 +504,38 @@ fn run_from_enemy(
     if let Some(move_dir) = best_dir {
         let avoid_vec = move_dir * max_plan;
         let behavior = &mut behaviors.squads[squad.squad as usize];
-        behavior.leader_position = Some(pos.xz() + avoid_vec);
+        behavior.go_to(pos.xz() + avoid_vec);
     }
 
     false
 }
 
+/// Swings out to the side of the target instead of approaching head on.
+///
+/// Returns a waypoint beside the target and then a throwing position on that
+/// side.
+fn plan_flank(
+    bounds: &Boundaries,
+    pos: Vec2,
+    target_pos: Vec2,
+    target_radius: f32,
+    throw_dist: f32,
+) -> [Vec2; 2] {
+    let aabb = bounds.aabb2();
+    let dir = (target_pos - pos).normalize_or_zero();
+
+    // Flank on the side with more room.
+    let mut side = dir.perp();
+    let center = aabb.center();
+    if (target_pos + side).distance(center) > (target_pos - side).distance(center) {
+        side = -side;
+    }
+
+    let swing = target_pos + (target_radius + 1.5 * throw_dist) * side - 0.5 * throw_dist * dir;
+    let attack = target_pos + throw_dist * side;
+    [swing, attack].map(|p| p.clamp(aabb.min, aabb.max))
+}
+
 struct RespawnSquad {
     team: Team,
     squad: Squad,
@@ -264,11 +554,13 @@ impl Command for RespawnSquad {
 }
 
 // Move the leader token back into the spawn area and respawn all players.
+#[allow(clippy::complexity)]
 fn spawn_squad(
     respawn: In<RespawnSquad>,
     mut commands: Commands,
     config: Res<GameConfig>,
     gym_params: Res<GymParams>,
+    mut rng: ResMut<MatchRng>,
     mut behaviors: ResMut<SquadBehaviors>,
     team_assets: Res<AllTeamAssets>,
     squad_assets: Res<AllSquadAssets>,
@@ -282,7 +574,13 @@ fn spawn_squad(
 
     commands.entity(behavior.leader).despawn_recursive();
 
-    let mut rng = rand::thread_rng();
+    // Respawned squads keep the size they bought.
+    let size = config.team_size(team.team());
+    let squad_in_team = squad.squad - config.squads_of_team(team.team()).start;
+    let points = config
+        .point_buy
+        .squad_points(team.team(), size.squads, true)[usize::from(squad_in_team)];
+
     let x = rng.gen_range(aabb.min.x..aabb.max.x);
     let z = rng.gen_range(aabb.min.y..aabb.max.y);
     let leader_pos = Vec2::new(x, z);
@@ -294,6 +592,7 @@ fn spawn_squad(
         squad.squad,
         aabb,
         leader_pos,
-        config.players_per_squad,
+        points.squad_size(size.players_per_squad),
+        &mut *rng,
     );
 }
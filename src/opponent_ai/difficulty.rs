@@ -0,0 +1,124 @@
+use crate::settings::GameConfig;
+use serde::{Deserialize, Serialize};
+
+/// How well the bots play.
+///
+/// `Normal` bots decide and react every tick. `Easy` bots are slower, and the
+/// harder bots aim better, judge threats better and work together.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum BotDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl BotDifficulty {
+    pub const ALL: [Self; 4] = [Self::Easy, Self::Normal, Self::Hard, Self::Insane];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+            Self::Insane => "Insane",
+        }
+    }
+
+    pub fn params(self) -> BotParams {
+        match self {
+            Self::Easy => BotParams {
+                reaction_seconds: 0.6,
+                decision_seconds: 0.5,
+                aim_spread_factor: 2.0,
+                max_ball_ratio: 1.5,
+                ball_factor: 0.2,
+                dist_factor: 0.8,
+                safety_margin: 1.25,
+                replan_distance: 8.0,
+                spread_out: 0.0,
+                gang_up: 0.0,
+            },
+            Self::Normal => BotParams {
+                reaction_seconds: 0.0,
+                decision_seconds: 0.0,
+                aim_spread_factor: 1.0,
+                max_ball_ratio: 2.0,
+                ball_factor: 0.5,
+                dist_factor: 0.5,
+                safety_margin: 2.0,
+                replan_distance: 5.0,
+                spread_out: 0.5,
+                gang_up: 0.0,
+            },
+            Self::Hard => BotParams {
+                reaction_seconds: 0.0,
+                decision_seconds: 0.0,
+                aim_spread_factor: 0.75,
+                max_ball_ratio: 3.0,
+                ball_factor: 0.6,
+                dist_factor: 0.4,
+                safety_margin: 2.0,
+                replan_distance: 4.0,
+                spread_out: 0.75,
+                gang_up: 0.5,
+            },
+            Self::Insane => BotParams {
+                reaction_seconds: 0.0,
+                decision_seconds: 0.0,
+                aim_spread_factor: 0.5,
+                max_ball_ratio: 4.0,
+                ball_factor: 0.7,
+                dist_factor: 0.3,
+                safety_margin: 2.5,
+                replan_distance: 3.0,
+                spread_out: 0.9,
+                gang_up: 1.0,
+            },
+        }
+    }
+}
+
+/// Tuning for the heuristic bot.
+#[derive(Clone, Copy, Debug)]
+pub struct BotParams {
+    /// How long a new threat or target has to stick around before a squad
+    /// reacts to it.
+    pub reaction_seconds: f32,
+    /// Time between decisions for each squad. Zero means every tick.
+    pub decision_seconds: f32,
+    /// Scales `PlayerStats::throw_spread` for bot squads.
+    pub aim_spread_factor: f32,
+    /// Ball ratios beyond this are all treated the same when assessing
+    /// threats.
+    pub max_ball_ratio: f32,
+    /// How much the ball ratio matters when assessing threats.
+    pub ball_factor: f32,
+    /// How much distance matters when assessing threats.
+    pub dist_factor: f32,
+    /// Squads run away until they are this many times the enemy's reach away.
+    pub safety_margin: f32,
+    /// Squads choose a new destination once they're this close to the old one.
+    pub replan_distance: f32,
+    /// From 0 to 1, how much squads avoid collecting balls where a teammate
+    /// is already headed.
+    pub spread_out: f32,
+    /// How much more attractive an enemy is when a teammate can already hit
+    /// it, as a fraction of its vulnerability.
+    pub gang_up: f32,
+}
+
+impl BotParams {
+    pub fn reaction_ticks(&self, config: &GameConfig) -> u64 {
+        seconds_to_ticks(self.reaction_seconds, config)
+    }
+
+    pub fn decision_ticks(&self, config: &GameConfig) -> u64 {
+        seconds_to_ticks(self.decision_seconds, config).max(1)
+    }
+}
+
+fn seconds_to_ticks(seconds: f32, config: &GameConfig) -> u64 {
+    (seconds / config.tick_seconds()).round() as u64
+}
@@ -4,18 +4,45 @@ use bevy::prelude::Vec2;
 pub const AVOID_FACTOR: f32 = 0.8;
 /// Try to avoid other players in some radius.
 pub const AVOID_RADIUS: f32 = 0.3;
+pub const BALL_GRID_CELL_SIZE: Vec2 = Vec2::splat(2.0);
+/// The default `SquadBehavior::ball_leash`.
+pub const BALL_LEASH: f32 = 5.0;
+/// Chance of losing the held ball after blocking with it.
+pub const BLOCK_FUMBLE_PROBABILITY: f32 = 0.3;
 pub const BLOOM_INTENSITY: f32 = 1.5;
+/// How far ball carriers move toward the front of their cluster, as a fraction
+/// of the cluster radius.
+pub const CARRIER_FRONT_FACTOR: f32 = 0.6;
+/// Balls slower than this are caught with the full `PlayerStats::catch_skill`.
+/// Faster balls are proportionally harder to catch.
+pub const CATCH_EASY_SPEED: f32 = 10.0;
+/// Catching with a ball already in hand is this much harder.
+pub const CATCH_HOLDING_FACTOR: f32 = 0.5;
 pub const CHASE_FACTOR: f32 = 0.2;
+/// How long knocked out players stay on the court, before they despawn or go
+/// to jail.
 pub const DESPAWN_SECONDS: u64 = 5;
+pub const DODGE_CELL_SIZE: Vec2 = Vec2::splat(2.0);
+/// How much to sidestep incoming thrown balls.
+pub const DODGE_FACTOR: f32 = 0.6;
+/// How far ahead to predict thrown ball paths.
+pub const DODGE_LOOKAHEAD_SECONDS: f32 = 0.75;
+/// Sidestep balls that are predicted to pass closer than this.
+pub const DODGE_RADIUS: f32 = 1.0;
+/// Leader tokens move a bit slower than players, so the squad can keep up.
+pub const LEADER_SPEED_FACTOR: f32 = 0.8;
+/// The slowest a leader token moves while waiting for stragglers, as a fraction
+/// of its full speed.
+pub const LEADER_MIN_SPEED_FRACTION: f32 = 0.2;
 pub const OCCUPANCY_CELL_SIZE: Vec2 = Vec2::splat(AVOID_RADIUS);
 pub const SQUAD_AI_COLLIDER_HEIGHT: f32 = 0.1;
 pub const SQUAD_AI_COLLIDER_RADIUS: f32 = 2.0;
 /// Players per square meter.
 pub const SQUAD_CLUSTER_DENSITY: f32 = 3.0;
-/// How far a thrown ball travels upwards on its trajectory.
-///
-/// This should be nonzero to avoid friendly fire.
+/// The default `SquadBehavior::throw_loft`.
 pub const THROW_LOFT: f32 = 0.1;
+/// The range of `SquadBehavior::throw_loft`.
+pub const THROW_LOFT_RANGE: std::ops::RangeInclusive<f32> = 0.1..=8.0;
 pub const THROW_SPREAD_ANGLE: f32 = 0.3;
 /// A percentage of the target's height.
 pub const THROW_TARGET_HEIGHT: f32 = 0.8;
@@ -25,3 +52,6 @@ pub const THROW_TARGET_HEIGHT: f32 = 0.8;
 /// throwing it.
 pub const THROW_COOLDOWN_MILLIS: u64 = 2000;
 pub const THROW_OVER_HEAD: f32 = 0.3;
+/// A squad has reached its leader position when its center of mass is this
+/// close.
+pub const WAYPOINT_RADIUS: f32 = 3.0;
@@ -1,20 +1,29 @@
 mod avoid_players;
+mod chase_balls;
+mod jail;
 mod knocked_out;
 
 pub use self::avoid_players::*;
+pub use self::jail::Jail;
 pub use self::knocked_out::KnockedOut;
 
 use self::knocked_out::DespawnTimer;
 use crate::{
-    ball::{BallAssets, ThrownBallBundle},
+    ball::{BallAssets, BallBundle, Thrower, ThrownBallBundle},
+    ball_paths::BallPaths,
     boundaries::Boundaries,
     collision,
+    formation::{slot_spacing, FormationSlot},
+    interpolation::TransformInterpolation,
+    match_rng::MatchRng,
     parameters::{
-        AVOID_FACTOR, CHASE_FACTOR, THROW_COOLDOWN_MILLIS, THROW_LOFT, THROW_OVER_HEAD,
-        THROW_SPREAD_ANGLE, THROW_TARGET_HEIGHT,
+        AVOID_FACTOR, CARRIER_FRONT_FACTOR, CHASE_FACTOR, DODGE_FACTOR, THROW_COOLDOWN_MILLIS,
+        THROW_OVER_HEAD, THROW_TARGET_HEIGHT,
     },
-    squad::{Squad, SquadAi, SquadAssets, SquadBehaviors, SquadStates},
+    settings::GameConfig,
+    squad::{Squad, SquadAi, SquadAssets, SquadBehavior, SquadBehaviors, SquadState, SquadStates},
     team::{AllTeamAssets, Team, TeamAssets},
+    visuals::VisualBundle,
 };
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::{
@@ -33,32 +42,49 @@ pub struct PlayerBundle {
     pub body: RigidBody,
     pub collider: Collider,
     pub collision_groups: CollisionGroups,
+    pub interpolation: TransformInterpolation,
     pub locked_axes: LockedAxes,
     pub mass: ColliderMassProperties,
-    pub pbr: PbrBundle,
     pub player: Player,
+    pub slot: FormationSlot,
     pub squad: Squad,
     pub team: Team,
     pub throw_cooldown: ThrowCooldown,
+    pub transform: TransformBundle,
     pub velocity: Velocity,
 }
 
 #[derive(Component, Default)]
 pub struct PlayerBall {
+    /// A loose ball this player is running to pick up.
     pub target_ball: Option<Entity>,
     pub chase_vector: Vec3,
+    /// True while `target_ball` is claimed, so no one else goes for it.
     pub claimed_ball: bool,
     pub holding_ball: bool,
 }
 
-impl PlayerBundle {
-    pub fn new(
-        team_assets: &TeamAssets,
-        squad_assets: &SquadAssets,
-        team: Team,
-        squad: u8,
+impl PlayerBall {
+    /// Replaces the held ball with a ground ball at the player's position.
+    pub fn drop_ball(
+        &mut self,
+        commands: &mut Commands,
+        ball_assets: &BallAssets,
+        bounds: &Boundaries,
+        player_entity: Entity,
         position: Vec3,
-    ) -> Self {
+    ) {
+        let mut dropped = commands.spawn(BallBundle::new_on_ground(ball_assets, bounds, position));
+        if let Some(visual) = &ball_assets.visual {
+            dropped.insert(visual.bundle());
+        }
+        commands.entity(player_entity).despawn_descendants();
+        self.holding_ball = false;
+    }
+}
+
+impl PlayerBundle {
+    pub fn new(team_assets: &TeamAssets, team: Team, squad: u8, slot: u32, position: Vec3) -> Self {
         Self {
             avoid_players: default(),
             ball: default(),
@@ -69,25 +95,39 @@ impl PlayerBundle {
                 team_assets.capsule_radius,
             ),
             collision_groups: Player::in_play_groups(),
+            interpolation: default(),
             // Prevents unatural amounts of spinning when hit with a ball.
             locked_axes: LockedAxes::ROTATION_LOCKED_Y,
             mass: ColliderMassProperties::Density(1.0),
             player: Player,
-            pbr: PbrBundle {
-                mesh: team_assets.mesh.clone(),
-                material: squad_assets.in_play_material.clone(),
-                transform: Transform::from_translation(position),
-                ..default()
-            },
+            slot: FormationSlot { index: slot },
             squad: Squad::new(squad),
             team,
             throw_cooldown: ThrowCooldown::new(),
+            transform: TransformBundle::from_transform(Transform::from_translation(position)),
             velocity: Velocity::zero(),
         }
     }
 }
 
 impl Player {
+    pub fn spawn(
+        commands: &mut Commands,
+        team_assets: &TeamAssets,
+        squad_assets: &SquadAssets,
+        team: Team,
+        squad: u8,
+        slot: u32,
+        position: Vec3,
+    ) -> Entity {
+        let mut player =
+            commands.spawn(PlayerBundle::new(team_assets, team, squad, slot, position));
+        if let (Some(mesh), Some(material)) = (&team_assets.mesh, &squad_assets.in_play_material) {
+            player.insert(VisualBundle::new(mesh.clone(), material.clone()));
+        }
+        player.id()
+    }
+
     fn in_play_groups() -> CollisionGroups {
         CollisionGroups::new(
             collision::groups::PLAYER,
@@ -97,28 +137,40 @@ impl Player {
         )
     }
 
-    fn out_of_play_groups() -> CollisionGroups {
+    pub fn out_of_play_groups() -> CollisionGroups {
         CollisionGroups::new(
             collision::groups::PLAYER,
             collision::groups::THROWN_BALL | collision::groups::BOUNDARIES,
         )
     }
 
+    fn jailed_groups() -> CollisionGroups {
+        CollisionGroups::new(collision::groups::PLAYER, collision::groups::BOUNDARIES)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn knock_out(
         commands: &mut Commands,
         assets: &AllTeamAssets,
+        jail: Option<&mut Jail>,
         entity: Entity,
         team: &Team,
         body: &mut RigidBody,
         groups: &mut CollisionGroups,
-        material: &mut Handle<StandardMaterial>,
+        material: Option<&mut Handle<StandardMaterial>>,
     ) {
         *body = RigidBody::Dynamic;
         *groups = Self::out_of_play_groups();
         let assets = &assets.teams[team.team() as usize];
-        *material = assets.out_of_play_material.clone();
+        if let (Some(material), Some(out_of_play_material)) =
+            (material, &assets.out_of_play_material)
+        {
+            *material = out_of_play_material.clone();
+        }
 
+        if let Some(jail) = jail {
+            jail.enqueue(entity, team.team());
+        }
         commands
             .entity(entity)
             .try_insert((KnockedOut, DespawnTimer::new()));
@@ -180,12 +232,22 @@ impl Player {
         behaviors: Res<SquadBehaviors>,
         states: Res<SquadStates>,
         mut players: Query<
-            (&Squad, &GlobalTransform, &mut Velocity),
+            (
+                &Squad,
+                &FormationSlot,
+                &PlayerBall,
+                &GlobalTransform,
+                &mut Velocity,
+            ),
             (With<Player>, Without<KnockedOut>),
         >,
         leader_transforms: Query<&GlobalTransform, With<SquadAi>>,
     ) {
-        for (squad, player_tfm, mut velocity) in &mut players {
+        for (squad, slot, player_ball, player_tfm, mut velocity) in &mut players {
+            if player_ball.target_ball.is_some() {
+                // Off to grab a ball.
+                continue;
+            }
             let behavior = &behaviors.squads[squad.squad as usize];
             let Ok(leader_tfm) = leader_transforms.get(behavior.leader) else {
                 continue;
@@ -195,6 +257,23 @@ impl Player {
             let leader_pos = leader_tfm.translation();
             let player_pos = player_tfm.translation();
 
+            let spacing = slot_spacing(behavior.cluster_density);
+            if let Some(offset) = behavior.formation.slot_offset(
+                slot.index,
+                state.num_players,
+                spacing,
+                behavior.facing,
+            ) {
+                // Each player runs to its own slot, so the shape holds without
+                // having to balance the center of mass. Ball carriers already
+                // have the front slots.
+                let slot_pos = leader_pos + Vec3::new(offset.x, 0.0, offset.y);
+                let mut to_slot = slot_pos - player_pos;
+                to_slot.y = 0.0;
+                velocity.linvel += CHASE_FACTOR * to_slot.clamp_length_max(1.0);
+                continue;
+            }
+
             // PERF: we could cache parts of this calculation in SquadState
             //
             // It's not good enough to just run to the leader. We want the
@@ -203,7 +282,13 @@ impl Player {
             // Each player moves in the direction that the center of mass needs
             // to move towards the target, but weighted by how far they are from
             // the center.
-            let player_to_leader = leader_pos - player_pos;
+            //
+            // Ball carriers aim for a spot in front of the leader, facing the
+            // throw target, and everyone else aims behind it. The offsets are
+            // weighted so they average to zero, which keeps the center of mass
+            // converging on the leader.
+            let front_offset = front_offset(state, leader_pos, player_ball.holding_ball);
+            let player_to_leader = leader_pos + front_offset - player_pos;
             let com_to_leader = leader_pos - state.center_of_mass;
             let player_to_leader_dist = player_to_leader.length();
             let player_to_cluster_dist = (player_to_leader_dist - state.cluster_radius).max(0.0);
@@ -235,14 +320,36 @@ impl Player {
         }
     }
 
+    #[allow(clippy::complexity)]
+    pub fn dodge_balls(
+        paths: Res<BallPaths>,
+        behaviors: Res<SquadBehaviors>,
+        mut players: Query<
+            (&Squad, &GlobalTransform, &mut Velocity),
+            (With<Player>, Without<KnockedOut>),
+        >,
+    ) {
+        players
+            .par_iter_mut()
+            .for_each(|(squad, tfm, mut velocity)| {
+                let stats = &behaviors.squads[squad.squad as usize].stats;
+                let sidestep = paths.sidestep(tfm.translation().xz());
+                if sidestep != Vec2::ZERO {
+                    velocity.linvel +=
+                        DODGE_FACTOR * stats.dodge * Vec3::new(sidestep.x, 0.0, sidestep.y);
+                }
+            });
+    }
+
     #[allow(clippy::complexity)]
     pub fn throw_ball_at_enemy(
         mut commands: Commands,
-        time: Res<Time>,
+        config: Res<GameConfig>,
+        mut rng: ResMut<MatchRng>,
         team_assets: Res<AllTeamAssets>,
         ball_assets: Res<BallAssets>,
         behaviors: Res<SquadBehaviors>,
-        states: Res<SquadStates>,
+        mut states: ResMut<SquadStates>,
         mut players: Query<
             (
                 Entity,
@@ -255,63 +362,116 @@ impl Player {
             Without<KnockedOut>,
         >,
     ) {
+        // Count who is ready first, so each squad can decide how many throws
+        // to allow this tick.
+        let mut num_ready = vec![0; states.squads.len()];
+        for (_, _, player_squad, player_tfm, player_ball, mut throw_cooldown) in &mut players {
+            if !player_ball.holding_ball {
+                continue;
+            }
+            if !throw_cooldown.timer.finished() {
+                throw_cooldown.timer.tick(config.tick_duration());
+            }
+            let squad = player_squad.squad as usize;
+            if aim_at_target(
+                &behaviors.squads[squad],
+                &states.squads[squad],
+                player_tfm,
+                &throw_cooldown,
+            )
+            .is_some()
+            {
+                num_ready[squad] += 1;
+            }
+        }
+        let mut throws_left: Vec<_> = states
+            .squads
+            .iter_mut()
+            .zip(&behaviors.squads)
+            .zip(num_ready)
+            .map(|((state, behavior), n)| state.schedule_throws(behavior, n, config.tick_seconds()))
+            .collect();
+
         for (
             player_entity,
             player_team,
             player_squad,
             player_tfm,
             mut player_ball,
-            mut throw_cooldown,
+            throw_cooldown,
         ) in &mut players
         {
             if !player_ball.holding_ball {
                 continue;
             }
 
-            if !throw_cooldown.timer.finished() {
-                throw_cooldown.timer.tick(time.delta());
-                continue;
-            }
-
-            let squad_state = &states.squads[player_squad.squad as usize];
-
-            let Some(throw_target) = squad_state.throw_target else {
-                // No players!
+            let squad = player_squad.squad as usize;
+            let behavior = &behaviors.squads[squad];
+            let stats = &behavior.stats;
+            let Some(enemy_vector) =
+                aim_at_target(behavior, &states.squads[squad], player_tfm, &throw_cooldown)
+            else {
                 continue;
             };
+            if throws_left[squad] == 0 {
+                continue;
+            }
+            throws_left[squad] -= 1;
             let player_pos = player_tfm.translation();
 
-            let stats = &behaviors.squads[player_squad.squad as usize].stats;
-
-            // Check if the enemy is within throwing distance.
-            let enemy_vector = throw_target - player_pos;
-            let enemy_dist = enemy_vector.length();
-            if enemy_dist <= stats.throw_distance {
-                // Despawn the held ball.
-                commands.entity(player_entity).despawn_descendants();
-                player_ball.holding_ball = false;
-
-                // Spawn a thrown ball.
-                // Start the throw over the player's heads so they don't friendly fire.
-                let mut rng = rand::thread_rng();
-                let angle_offset = rng.gen_range(-THROW_SPREAD_ANGLE..THROW_SPREAD_ANGLE);
-                let throw_vector = Mat2::from_angle(angle_offset) * enemy_vector.xz();
-                let player_height = team_assets.teams[player_team.team() as usize].size.y;
-                let start_y = player_height + THROW_OVER_HEAD;
-                let max_y = start_y + THROW_LOFT;
-                let end_y = THROW_TARGET_HEIGHT * player_height; // TODO: should look at other team's height
-                let throw_v = throw_velocity(throw_vector, start_y, max_y, end_y);
-                let throw_start = Vec3::new(player_pos.x, start_y, player_pos.z);
-                commands.spawn(ThrownBallBundle::new(&ball_assets, throw_start, throw_v));
+            // Despawn the held ball.
+            commands.entity(player_entity).despawn_descendants();
+            player_ball.holding_ball = false;
+
+            // Spawn a thrown ball.
+            // Start the throw over the player's heads so they don't friendly fire.
+            let spread = stats.throw_spread;
+            let angle_offset = if spread > 0.0 {
+                rng.gen_range(-spread..spread)
             } else {
-                // Run towards the enemy.
-                // TODO: enable but prioritize against following squad AI
-                // target_enemy.chase_vector = enemy_direction;
+                0.0
+            };
+            let throw_vector = Mat2::from_angle(angle_offset) * enemy_vector.xz();
+            let player_height = team_assets.teams[player_team.team() as usize].size.y;
+            let (start_y, end_y) = throw_heights(player_height);
+            let max_y = start_y + behavior.throw_loft;
+            let (throw_v, _) = throw_velocity(throw_vector, start_y, max_y, end_y);
+            let throw_start = Vec3::new(player_pos.x, start_y, player_pos.z);
+            let mut ball = commands.spawn(ThrownBallBundle::new(
+                &ball_assets,
+                throw_start,
+                throw_v,
+                Thrower {
+                    entity: player_entity,
+                    team: player_team.team(),
+                },
+            ));
+            if let Some(visual) = &ball_assets.visual {
+                ball.insert(visual.bundle());
             }
         }
     }
 }
 
+/// The vector to the squad's throw target, if the player could throw at it
+/// right now.
+fn aim_at_target(
+    behavior: &SquadBehavior,
+    state: &SquadState,
+    player_tfm: &GlobalTransform,
+    throw_cooldown: &ThrowCooldown,
+) -> Option<Vec3> {
+    if !throw_cooldown.timer.finished() {
+        return None;
+    }
+    // No target when there are no enemies.
+    let throw_target = state.throw_target?;
+    let enemy_vector = throw_target - player_tfm.translation();
+    // TODO: run towards the enemy when out of range, but prioritize following
+    // the squad AI
+    (enemy_vector.length() <= behavior.stats.throw_distance).then_some(enemy_vector)
+}
+
 #[derive(Component)]
 pub struct ThrowCooldown {
     pub timer: Timer,
@@ -325,8 +485,58 @@ impl ThrowCooldown {
     }
 }
 
-fn throw_velocity(lateral_displacement: Vec2, start_y: f32, max_y: f32, end_y: f32) -> Vec3 {
-    let g = 9.80665;
+/// Where a player should cluster relative to the leader, so ball carriers end
+/// up on the side facing the throw target.
+fn front_offset(state: &SquadState, leader_pos: Vec3, holding_ball: bool) -> Vec3 {
+    let Some(throw_target) = state.throw_target else {
+        return Vec3::ZERO;
+    };
+    let mut front = throw_target - leader_pos;
+    front.y = 0.0;
+    let front = front.normalize_or_zero();
+
+    // If a fraction f of players are carriers, carriers move (1 - f) forward
+    // and the others move f back, so the average offset is zero.
+    let carrier_fraction = state.num_holding_balls as f32 / state.num_players.max(1) as f32;
+    let shift = if holding_ball {
+        1.0 - carrier_fraction
+    } else {
+        -carrier_fraction
+    };
+    CARRIER_FRONT_FACTOR * state.cluster_radius * shift * front
+}
+
+/// Points along the path of a ball thrown by a player at `from` toward
+/// `target`, for previewing throws.
+pub fn throw_arc(
+    player_height: f32,
+    from: Vec3,
+    target: Vec3,
+    loft: f32,
+) -> impl Iterator<Item = Vec3> {
+    let (start_y, end_y) = throw_heights(player_height);
+    let start = Vec3::new(from.x, start_y, from.z);
+    let (v, travel_time) = throw_velocity((target - from).xz(), start_y, start_y + loft, end_y);
+    let n_points = 32;
+    (0..=n_points).map(move |i| {
+        let t = travel_time * i as f32 / n_points as f32;
+        start + t * v - 0.5 * GRAVITY * t * t * Vec3::Y
+    })
+}
+
+/// Throws start over the thrower's head, so they don't hit teammates right
+/// away, and end at the target's chest.
+fn throw_heights(player_height: f32) -> (f32, f32) {
+    let start_y = player_height + THROW_OVER_HEAD;
+    let end_y = THROW_TARGET_HEIGHT * player_height; // TODO: should look at other team's height
+    (start_y, end_y)
+}
+
+const GRAVITY: f32 = 9.80665;
+
+/// Returns the launch velocity and the flight time.
+fn throw_velocity(lateral_displacement: Vec2, start_y: f32, max_y: f32, end_y: f32) -> (Vec3, f32) {
+    let g = GRAVITY;
 
     // Constant-acceleration kinematic equation: 0 = (1/2)at^2 + height
     let rise_time = (2.0 * (max_y - start_y) / g).sqrt();
@@ -341,5 +551,5 @@ fn throw_velocity(lateral_displacement: Vec2, start_y: f32, max_y: f32, end_y: f
     // Conservation of energy: mgh = (1/2)mv^2
     let v_y = (2.0 * g * (max_y - start_y)).sqrt();
 
-    Vec3::new(v_x, v_y, v_z)
+    (Vec3::new(v_x, v_y, v_z), travel_time)
 }
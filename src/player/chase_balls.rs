@@ -0,0 +1,107 @@
+use super::{KnockedOut, Player, PlayerBall};
+use crate::{
+    ball::Ball,
+    ball_grid::{BallGrid, GroundBall},
+    geometry::Circle,
+    squad::{Squad, SquadBehaviors, SquadStates},
+};
+use bevy::{prelude::*, utils::HashSet};
+
+impl Player {
+    /// Empty-handed players claim the nearest loose ball within their squad's
+    /// `SquadBehavior::ball_leash` and run to it. Each ball is claimed by at
+    /// most one player.
+    ///
+    /// Picking up the ball ends the chase, and following the leader brings the
+    /// player back.
+    #[allow(clippy::complexity)]
+    pub fn chase_balls(
+        behaviors: Res<SquadBehaviors>,
+        states: Res<SquadStates>,
+        grid: Res<BallGrid>,
+        balls: Query<(&Ball, &GlobalTransform)>,
+        mut players: Query<
+            (&Squad, &GlobalTransform, &mut PlayerBall),
+            (With<Player>, Without<KnockedOut>),
+        >,
+    ) {
+        let loose_ball_position = |entity| {
+            let (ball, tfm) = balls.get(entity).ok()?;
+            ball.is_loose().then(|| tfm.translation())
+        };
+        let reach = |squad: &Squad| {
+            let state = &states.squads[squad.squad as usize];
+            let leash = behaviors.squads[squad.squad as usize].ball_leash;
+            (state.num_players > 0)
+                .then(|| Circle::new(state.center_of_mass.xz(), state.cluster_radius + leash))
+        };
+
+        // Keep the claims that are still good.
+        let mut claimed = HashSet::new();
+        for (squad, _, mut player_ball) in &mut players {
+            let Some(target) = player_ball.target_ball else {
+                continue;
+            };
+            let in_reach =
+                loose_ball_position(target)
+                    .zip(reach(squad))
+                    .is_some_and(|(position, reach)| {
+                        reach.center.distance(position.xz()) <= reach.radius
+                    });
+            let keep = !player_ball.holding_ball && in_reach && claimed.insert(target);
+            if !keep {
+                player_ball.stop_chasing();
+            }
+        }
+
+        // Loose balls in reach of each squad.
+        let reachable: Vec<Vec<GroundBall>> = (0..states.squads.len())
+            .map(|squad| {
+                let Some(reach) = reach(&Squad::new(squad as u8)) else {
+                    return Vec::new();
+                };
+                grid.in_circle(reach)
+                    .filter(|b| !claimed.contains(&b.entity))
+                    .collect()
+            })
+            .collect();
+
+        for (squad, tfm, mut player_ball) in &mut players {
+            if player_ball.holding_ball {
+                continue;
+            }
+            let position = tfm.translation();
+
+            if player_ball.target_ball.is_none() {
+                let nearest = reachable[squad.squad as usize]
+                    .iter()
+                    .filter(|b| !claimed.contains(&b.entity))
+                    .min_by(|a, b| {
+                        let a = a.position.distance_squared(position.xz());
+                        let b = b.position.distance_squared(position.xz());
+                        a.total_cmp(&b)
+                    });
+                if let Some(nearest) = nearest {
+                    claimed.insert(nearest.entity);
+                    player_ball.target_ball = Some(nearest.entity);
+                    player_ball.claimed_ball = true;
+                }
+            }
+
+            let Some(target_pos) = player_ball.target_ball.and_then(loose_ball_position) else {
+                continue;
+            };
+            let mut to_ball = target_pos - position;
+            to_ball.y = 0.0;
+            player_ball.chase_vector = to_ball.normalize_or_zero();
+        }
+    }
+}
+
+impl PlayerBall {
+    pub fn stop_chasing(&mut self) {
+        self.target_ball = None;
+        self.claimed_ball = false;
+        self.chase_vector = Vec3::ZERO;
+    }
+}
@@ -0,0 +1,134 @@
+use super::{knocked_out::DespawnTimer, KnockedOut, Player};
+use crate::{
+    geometry::Aabb2,
+    squad::{AllSquadAssets, Squad, SquadAi, SquadBehaviors},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{CollisionGroups, RigidBody, Velocity};
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// The sideline area where knocked out players wait to be caught back in.
+///
+/// Only exists when `GameConfig::catch_in` is enabled. Otherwise knocked out
+/// players are despawned.
+#[derive(Resource)]
+pub struct Jail {
+    aabbs: [Aabb2; 2],
+    /// Knocked out players of each team, longest-waiting first.
+    queues: [VecDeque<Entity>; 2],
+    /// Catches that haven't freed a player yet.
+    pending: [u32; 2],
+}
+
+impl Jail {
+    pub fn new(aabbs: [Aabb2; 2]) -> Self {
+        Self {
+            aabbs,
+            queues: default(),
+            pending: [0; 2],
+        }
+    }
+
+    /// Puts a player who was just knocked out in line to be caught back in.
+    ///
+    /// This happens right away, so catches count while they are still on the
+    /// court.
+    pub fn enqueue(&mut self, entity: Entity, team: u8) {
+        self.queues[team as usize].push_back(entity);
+    }
+
+    /// Moves a knocked out player off the court and into their team's jail.
+    ///
+    /// Jails are on the sidelines, inside the walls, so jailed players stop
+    /// colliding with thrown balls to stay out of the game.
+    #[allow(clippy::too_many_arguments)]
+    pub fn lock_up(
+        &self,
+        team: u8,
+        height: f32,
+        transform: &mut Transform,
+        body: &mut RigidBody,
+        groups: &mut CollisionGroups,
+        velocity: &mut Velocity,
+        rng: &mut impl Rng,
+    ) {
+        let aabb = self.aabbs[team as usize];
+        let x = rng.gen_range(aabb.min.x..aabb.max.x);
+        let z = rng.gen_range(aabb.min.y..aabb.max.y);
+        *transform = Transform::from_xyz(x, 0.5 * height, z);
+        *body = RigidBody::KinematicPositionBased;
+        *groups = Player::jailed_groups();
+        *velocity = Velocity::zero();
+    }
+
+    /// A player on `team` caught a ball, so their longest-waiting teammate
+    /// gets to return.
+    ///
+    /// As in real dodgeball, a catch while nobody on the team is out frees
+    /// nobody. It isn't saved for players knocked out later.
+    pub fn catch_in(&mut self, team: u8) {
+        let team = team as usize;
+        if (self.pending[team] as usize) < self.queues[team].len() {
+            self.pending[team] += 1;
+        }
+    }
+
+    #[allow(clippy::complexity)]
+    pub fn free_players(
+        mut commands: Commands,
+        mut jail: ResMut<Self>,
+        behaviors: Res<SquadBehaviors>,
+        all_squad_assets: Res<AllSquadAssets>,
+        squad_ais: Query<(), With<SquadAi>>,
+        mut players: Query<
+            (
+                &Squad,
+                Has<DespawnTimer>,
+                &mut RigidBody,
+                &mut CollisionGroups,
+                Option<&mut Handle<StandardMaterial>>,
+            ),
+            (With<Player>, With<KnockedOut>),
+        >,
+    ) {
+        let jail = &mut *jail;
+        for (queue, pending) in jail.queues.iter_mut().zip(&mut jail.pending) {
+            while *pending > 0 {
+                let Some(entity) = queue.pop_front() else {
+                    // Everyone who was waiting belonged to an eliminated
+                    // squad, so the catch frees nobody.
+                    *pending = 0;
+                    break;
+                };
+                let Ok((squad, on_court, mut body, mut groups, material)) = players.get_mut(entity)
+                else {
+                    continue;
+                };
+                let leader = behaviors.squads[squad.squad as usize].leader;
+                if !squad_ais.contains(leader) {
+                    // The squad was eliminated, so there's nothing to return to.
+                    continue;
+                }
+                if on_court {
+                    // Still falling where they were knocked out. They go free
+                    // once they reach the jail.
+                    queue.push_front(entity);
+                    break;
+                }
+
+                *pending -= 1;
+                *body = RigidBody::KinematicVelocityBased;
+                *groups = Player::in_play_groups();
+                let squad_assets = &all_squad_assets.squads[squad.squad as usize];
+                if let (Some(mut material), Some(in_play_material)) =
+                    (material, &squad_assets.in_play_material)
+                {
+                    *material = in_play_material.clone();
+                }
+                // Following the leader will bring them back onto the court.
+                commands.entity(entity).remove::<KnockedOut>();
+            }
+        }
+    }
+}
@@ -1,40 +1,87 @@
-use super::PlayerBall;
+use super::{Jail, PlayerBall};
 use crate::{
-    ball::{BallAssets, BallBundle},
+    ball::BallAssets,
     boundaries::Boundaries,
+    match_rng::MatchRng,
     parameters::DESPAWN_SECONDS,
+    settings::GameConfig,
+    team::{AllTeamAssets, Team},
 };
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::{CollisionGroups, RigidBody, Velocity};
 use std::time::Duration;
 
 #[derive(Component)]
 pub struct KnockedOut;
 
 impl KnockedOut {
+    #[allow(clippy::complexity)]
     pub fn update(
         mut commands: Commands,
-        time: Res<Time>,
+        config: Res<GameConfig>,
         bounds: Res<Boundaries>,
         ball_assets: Res<BallAssets>,
+        team_assets: Res<AllTeamAssets>,
+        mut rng: ResMut<MatchRng>,
+        jail: Option<Res<Jail>>,
         mut players: Query<
-            (Entity, &GlobalTransform, &mut PlayerBall, &mut DespawnTimer),
+            (
+                Entity,
+                &Team,
+                &GlobalTransform,
+                &mut Transform,
+                &mut RigidBody,
+                &mut CollisionGroups,
+                &mut Velocity,
+                &mut PlayerBall,
+                &mut DespawnTimer,
+            ),
             With<KnockedOut>,
         >,
     ) {
-        for (entity, tfm, mut ball, mut timer) in &mut players {
+        for (
+            entity,
+            team,
+            tfm,
+            mut transform,
+            mut body,
+            mut groups,
+            mut velocity,
+            mut ball,
+            mut timer,
+        ) in &mut players
+        {
             if ball.holding_ball {
                 // Drop the ball.
                 // BUG: it's somewhat common for many balls to accumulate on top
                 // of each other when a cluster is knocked out quickly
                 // TODO: preserve the player's original ball and make it dynamic?
-                let position = tfm.translation();
-                commands.spawn(BallBundle::new_on_ground(&ball_assets, &bounds, position));
-                commands.entity(entity).despawn_descendants();
-                ball.holding_ball = false;
+                ball.drop_ball(
+                    &mut commands,
+                    &ball_assets,
+                    &bounds,
+                    entity,
+                    tfm.translation(),
+                );
             }
 
-            timer.timer.tick(time.delta());
-            if timer.timer.finished() {
+            timer.timer.tick(config.tick_duration());
+            if !timer.timer.finished() {
+                continue;
+            }
+            if let Some(jail) = jail.as_deref() {
+                let height = team_assets.teams[team.team() as usize].size.y;
+                jail.lock_up(
+                    team.team(),
+                    height,
+                    &mut transform,
+                    &mut body,
+                    &mut groups,
+                    &mut velocity,
+                    &mut *rng,
+                );
+                commands.entity(entity).remove::<DespawnTimer>();
+            } else {
                 commands.entity(entity).despawn();
             }
         }
@@ -0,0 +1,190 @@
+use crate::squad::PlayerStats;
+use bevy::{log::warn, prelude::default};
+use serde::{Deserialize, Serialize};
+
+/// Each point improves a stat by a fixed step over `PlayerStats::default()`.
+const SPEED_PER_POINT: f32 = 0.5;
+const THROW_RANGE_PER_POINT: f32 = 1.0;
+/// Each point divides the default throw spread by one more of this fraction.
+const ACCURACY_PER_POINT: f32 = 0.1;
+const CATCH_SKILL_PER_POINT: f32 = 0.05;
+/// Each point adds this fraction of `GameConfig::players_per_squad`.
+const SQUAD_SIZE_PER_POINT: f32 = 0.1;
+
+/// Points that one squad has bought.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StatPoints {
+    pub speed: u32,
+    pub throw_range: u32,
+    pub accuracy: u32,
+    pub catch_skill: u32,
+    pub squad_size: u32,
+}
+
+impl StatPoints {
+    pub fn total(&self) -> u32 {
+        self.speed + self.throw_range + self.accuracy + self.catch_skill + self.squad_size
+    }
+
+    pub fn stats(&self) -> PlayerStats {
+        let base = PlayerStats::default();
+        PlayerStats {
+            run_speed: base.run_speed + SPEED_PER_POINT * self.speed as f32,
+            throw_distance: base.throw_distance + THROW_RANGE_PER_POINT * self.throw_range as f32,
+            throw_spread: base.throw_spread / (1.0 + ACCURACY_PER_POINT * self.accuracy as f32),
+            catch_skill: (base.catch_skill + CATCH_SKILL_PER_POINT * self.catch_skill as f32)
+                .min(1.0),
+            ..base
+        }
+    }
+
+    pub fn squad_size(&self, players_per_squad: u32) -> u32 {
+        let extra = SQUAD_SIZE_PER_POINT * self.squad_size as f32 * players_per_squad as f32;
+        players_per_squad + extra.round() as u32
+    }
+
+    /// Mutable access to each stat, with a label for the UI.
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut u32); 5] {
+        [
+            ("Speed", &mut self.speed),
+            ("Throw Range", &mut self.throw_range),
+            ("Accuracy", &mut self.accuracy),
+            ("Catch Skill", &mut self.catch_skill),
+            ("Squad Size", &mut self.squad_size),
+        ]
+    }
+}
+
+/// Ready-made ways to spend a team's points. Bots always use one.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StatPreset {
+    #[default]
+    Balanced,
+    Runners,
+    Snipers,
+    Catchers,
+    Horde,
+}
+
+impl StatPreset {
+    pub const ALL: [Self; 5] = [
+        Self::Balanced,
+        Self::Runners,
+        Self::Snipers,
+        Self::Catchers,
+        Self::Horde,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Balanced => "Balanced",
+            Self::Runners => "Runners",
+            Self::Snipers => "Snipers",
+            Self::Catchers => "Catchers",
+            Self::Horde => "Horde",
+        }
+    }
+
+    /// Relative spending on speed, throw range, accuracy, catch skill and
+    /// squad size.
+    fn weights(self) -> [u32; 5] {
+        match self {
+            Self::Balanced => [1, 1, 1, 1, 1],
+            Self::Runners => [4, 1, 1, 1, 1],
+            Self::Snipers => [1, 3, 3, 0, 1],
+            Self::Catchers => [1, 0, 1, 4, 1],
+            Self::Horde => [0, 1, 0, 1, 4],
+        }
+    }
+
+    /// Spends exactly `points` in proportion to the preset's weights.
+    pub fn allocate(self, points: u32) -> StatPoints {
+        let weights = self.weights();
+        let total_weight: u32 = weights.iter().sum();
+        let mut spent = weights.map(|w| points * w / total_weight);
+
+        // Hand out what's left by largest remainder, breaking ties in field
+        // order so the result is deterministic.
+        let mut order: [usize; 5] = std::array::from_fn(|i| i);
+        order.sort_by_key(|&i| std::cmp::Reverse(points * weights[i] % total_weight));
+        let leftover = points - spent.iter().sum::<u32>();
+        for &i in order.iter().take(leftover as usize) {
+            spent[i] += 1;
+        }
+
+        let [speed, throw_range, accuracy, catch_skill, squad_size] = spent;
+        StatPoints {
+            speed,
+            throw_range,
+            accuracy,
+            catch_skill,
+            squad_size,
+        }
+    }
+}
+
+/// How one team spends its budget.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct TeamPoints {
+    /// When set, the budget is split evenly across squads using this preset
+    /// and `squads` is ignored.
+    pub preset: Option<StatPreset>,
+    /// Hand-picked points for each squad. Missing squads get no points.
+    pub squads: Vec<StatPoints>,
+}
+
+/// Stat points that each team spends across its squads before a match.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PointBuy {
+    /// Points available to each team. Zero means every squad has the default
+    /// stats.
+    pub budget: u32,
+    pub teams: [TeamPoints; 2],
+}
+
+impl PointBuy {
+    /// The points bought by each squad on `team`. Bot teams without a preset
+    /// use `StatPreset::default()`.
+    ///
+    /// A hand-picked allocation over budget is ignored.
+    pub fn squad_points(&self, team: u8, squads_per_team: u8, bot: bool) -> Vec<StatPoints> {
+        let n_squads = u32::from(squads_per_team.max(1));
+        let team_points = &self.teams[team as usize];
+        let preset = if bot {
+            Some(team_points.preset.unwrap_or_default())
+        } else {
+            team_points.preset
+        };
+
+        if let Some(preset) = preset {
+            return (0..n_squads)
+                .map(|i| {
+                    let extra = u32::from(i < self.budget % n_squads);
+                    preset.allocate(self.budget / n_squads + extra)
+                })
+                .collect();
+        }
+
+        let mut squads = team_points.squads.clone();
+        squads.resize(n_squads as usize, default());
+        let spent = self.spent(team, squads_per_team);
+        if spent > self.budget {
+            warn!(
+                "Ignoring stat points: team {team} spent {spent} of {} points",
+                self.budget
+            );
+            return vec![default(); n_squads as usize];
+        }
+        squads
+    }
+
+    /// Hand-picked points spent by `team`.
+    pub fn spent(&self, team: u8, squads_per_team: u8) -> u32 {
+        self.teams[team as usize]
+            .squads
+            .iter()
+            .take(usize::from(squads_per_team))
+            .map(StatPoints::total)
+            .sum()
+    }
+}
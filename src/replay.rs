@@ -0,0 +1,424 @@
+use crate::{
+    controller::ControllerKind,
+    game_state::GameState,
+    match_progress::MatchProgress,
+    restart_game::{load_game, RestartGame},
+    settings::GameConfig,
+    squad::SquadCommand,
+    tick::{run_ticks, SimulationTick},
+    time_controls::TimeControls,
+};
+use bevy::{ecs::system::Command, prelude::*};
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Bump this whenever a change to the simulation, `GameConfig` or the file
+/// format would make old replays play back differently.
+pub const REPLAY_VERSION: u32 = 3;
+
+pub const REPLAY_DIR: &str = "replays";
+
+/// Everything needed to reconstruct a match: the config (including the seed),
+/// who controlled each team and every command given to the squads in each
+/// round.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Replay {
+    pub version: u32,
+    /// The config of the first round. Each later round adds one to the seed.
+    pub config: GameConfig,
+    /// Bots run again during playback. Everyone else's orders are recorded.
+    pub controllers: [ControllerKind; 2],
+    pub rounds: Vec<RoundReplay>,
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct RoundReplay {
+    /// The number of ticks that were recorded.
+    pub length: u64,
+    /// Commands by the tick they were applied at.
+    pub commands: BTreeMap<u64, Vec<SquadCommand>>,
+}
+
+impl Replay {
+    pub fn new(config: GameConfig, controllers: [ControllerKind; 2]) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            config,
+            controllers,
+            rounds: vec![default()],
+        }
+    }
+
+    /// `round` counts from zero.
+    pub fn commands_at(&self, round: usize, tick: u64) -> &[SquadCommand] {
+        self.rounds
+            .get(round)
+            .and_then(|r| r.commands.get(&tick))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The number of ticks recorded over all rounds.
+    pub fn length(&self) -> u64 {
+        self.rounds.iter().map(|r| r.length).sum()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = io::BufWriter::new(fs::File::create(path)?);
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+
+    /// Fails on replays from other versions, and on commands that can't be
+    /// applied to the replay's squads.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = io::BufReader::new(fs::File::open(path)?);
+        let replay: Self = serde_json::from_reader(file)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(invalid_data(format!(
+                "replay version {} is not supported (expected {REPLAY_VERSION})",
+                replay.version
+            )));
+        }
+        let n_squads = usize::from(replay.config.squads_of_team(1).end);
+        for (round, round_replay) in replay.rounds.iter().enumerate() {
+            for (tick, commands) in &round_replay.commands {
+                if let Some(command) = commands.iter().find(|c| !c.is_valid(n_squads)) {
+                    return Err(invalid_data(format!(
+                        "invalid command in round {} at tick {tick}: {command:?}",
+                        round + 1
+                    )));
+                }
+            }
+        }
+        Ok(replay)
+    }
+
+    pub fn default_path(&self) -> PathBuf {
+        Path::new(REPLAY_DIR).join(format!(
+            "replay-{}-{}.json",
+            self.config.seed,
+            self.length()
+        ))
+    }
+
+    /// All replay files in `REPLAY_DIR`, sorted by name.
+    pub fn list_files() -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(REPLAY_DIR) else {
+            return Vec::new();
+        };
+        let mut files: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Records the commands of the current match.
+///
+/// The recorder is kept between rounds.
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    pub fn new(config: GameConfig, controllers: [ControllerKind; 2]) -> Self {
+        Self {
+            replay: Replay::new(config, controllers),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, command: SquadCommand) {
+        self.current_round()
+            .commands
+            .entry(tick)
+            .or_default()
+            .push(command);
+    }
+
+    /// Ends the current round after `tick` ticks.
+    pub fn next_round(&mut self, tick: &SimulationTick) {
+        self.current_round().length = tick.0;
+        self.replay.rounds.push(default());
+    }
+
+    /// The replay up to `tick` of the current round.
+    pub fn finish(&self, tick: &SimulationTick) -> Replay {
+        let mut replay = self.replay.clone();
+        if let Some(round) = replay.rounds.last_mut() {
+            round.length = tick.0;
+        }
+        replay
+    }
+
+    fn current_round(&mut self) -> &mut RoundReplay {
+        // `Replay::new` starts with a round.
+        self.replay.rounds.last_mut().unwrap()
+    }
+}
+
+/// Present while a replay is being watched. Squads then follow the recorded
+/// commands instead of human input.
+#[derive(Resource)]
+pub struct Playback {
+    pub replay: Replay,
+}
+
+impl Playback {
+    #[allow(clippy::complexity)]
+    pub fn draw(
+        mut commands: Commands,
+        playback: Res<Self>,
+        progress: Res<MatchProgress>,
+        tick: Res<SimulationTick>,
+        state: Res<State<GameState>>,
+        mut next_state: ResMut<NextState<GameState>>,
+        mut time_controls: ResMut<TimeControls>,
+        mut seek_tick: Local<Option<u64>>,
+        mut contexts: EguiContexts,
+    ) {
+        let round = progress.rounds.len();
+        let n_rounds = playback.replay.rounds.len();
+        let length = playback.replay.rounds.get(round).map_or(0, |r| r.length);
+        egui::Window::new("Replay")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 0.0))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.label(format!(
+                    "round {} / {n_rounds}, tick {} / {length}",
+                    round + 1,
+                    tick.0
+                ));
+                ui.horizontal(|ui| {
+                    let paused = *state.get() == GameState::Paused;
+                    let pause_text = if paused { "Resume" } else { "Pause" };
+                    if ui.button(pause_text).clicked() {
+                        if paused {
+                            next_state.set(GameState::Playing);
+                        } else {
+                            next_state.set(GameState::Paused);
+                        }
+                    }
+                    let mut speed = time_controls.speed;
+                    if ui
+                        .add(egui::Slider::new(&mut speed, 0.25..=8.0).text("Speed"))
+                        .changed()
+                    {
+                        time_controls.speed = speed;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let target = seek_tick.get_or_insert(tick.0);
+                    ui.add(egui::Slider::new(target, 0..=length).text("Tick"));
+                    if ui.button("Seek").clicked() {
+                        commands.add(SeekReplay {
+                            round,
+                            tick: *target,
+                        });
+                        *seek_tick = None;
+                    }
+                });
+                if ui.button("Exit Replay").clicked() {
+                    commands.add(StopPlayback);
+                }
+            });
+    }
+}
+
+/// Restarts the game as a playback of `replay`.
+pub struct WatchReplay(pub Replay);
+
+impl Command for WatchReplay {
+    fn apply(self, world: &mut World) {
+        world.insert_resource(Playback { replay: self.0 });
+        RestartGame.apply(world);
+    }
+}
+
+/// Re-simulates the replay from the start up to `tick` of `round`, which
+/// counts from zero.
+pub struct SeekReplay {
+    pub round: usize,
+    pub tick: u64,
+}
+
+impl Command for SeekReplay {
+    fn apply(self, world: &mut World) {
+        let paused = *world.resource::<State<GameState>>().get() == GameState::Paused;
+        load_game(world);
+        GameState::transition_now(world, GameState::Playing);
+        loop {
+            let progress = world.resource::<MatchProgress>();
+            let now = (progress.rounds.len(), world.resource::<SimulationTick>().0);
+            if progress.finished || now >= (self.round, self.tick) {
+                break;
+            }
+            run_ticks(world, 1);
+        }
+        if paused {
+            GameState::transition_now(world, GameState::Paused);
+        }
+    }
+}
+
+pub struct StopPlayback;
+
+impl Command for StopPlayback {
+    fn apply(self, world: &mut World) {
+        world.remove_resource::<Playback>();
+        world.resource_mut::<TimeControls>().speed = 1.0;
+        RestartGame.apply(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ball::Ball,
+        controller::{TeamController, TeamControllers},
+        formation::Formation,
+        headless::HeadlessMatch,
+        player::Player,
+        scoreboard::ScoreBoard,
+        settings::{GameMode, GameSettings},
+        squad::SquadKnobs,
+    };
+
+    fn config() -> GameConfig {
+        GameConfig {
+            mode: GameMode::Match,
+            seed: 7,
+            ..default()
+        }
+    }
+
+    fn snapshot(world: &mut World) -> (u64, Vec<u32>, Vec<Vec3>, Vec<Vec3>) {
+        let tick = world.resource::<SimulationTick>().0;
+        let scores = world.resource::<ScoreBoard>().team_scores.clone();
+        let players = world
+            .query_filtered::<&GlobalTransform, With<Player>>()
+            .iter(world)
+            .map(GlobalTransform::translation)
+            .collect();
+        let balls = world
+            .query_filtered::<&GlobalTransform, With<Ball>>()
+            .iter(world)
+            .map(GlobalTransform::translation)
+            .collect();
+        (tick, scores, players, balls)
+    }
+
+    fn record() -> Replay {
+        let mut game =
+            HeadlessMatch::with_controllers(config(), default(), TeamControllers::with_human(0));
+        game.queue_command(SquadCommand::SetLeaderPosition {
+            squad: 0,
+            position: [0.0, 10.0],
+        });
+        game.run_ticks(100);
+        game.queue_command(SquadCommand::SetKnobs {
+            squad: 0,
+            knobs: SquadKnobs {
+                formation: Some(Formation::Line),
+                ..default()
+            },
+        });
+        game.run_ticks(800);
+        let world = game.world();
+        world
+            .resource::<ReplayRecorder>()
+            .finish(world.resource::<SimulationTick>())
+    }
+
+    #[test]
+    fn seeking_matches_playing() {
+        let replay = record();
+        let mut game = HeadlessMatch::new(config(), default());
+        let world = game.world_mut();
+        WatchReplay(replay).apply(world);
+        GameState::transition_now(world, GameState::Loading);
+        GameState::transition_now(world, GameState::Playing);
+
+        run_ticks(world, 700);
+        let played = snapshot(world);
+        run_ticks(world, 100);
+        SeekReplay {
+            round: 0,
+            tick: 700,
+        }
+        .apply(world);
+        assert_eq!(snapshot(world), played);
+    }
+
+    #[test]
+    fn playback_uses_recorded_controllers() {
+        let controllers = TeamControllers {
+            teams: [TeamController::Human, TeamController::Human],
+        };
+        let mut game = HeadlessMatch::with_controllers(config(), default(), controllers);
+        game.run_ticks(600);
+        let world = game.world_mut();
+        let played = snapshot(world);
+        let replay = world
+            .resource::<ReplayRecorder>()
+            .finish(world.resource::<SimulationTick>());
+
+        // Both teams would be bots if the local controllers were used.
+        let mut game = HeadlessMatch::new(config(), default());
+        let world = game.world_mut();
+        WatchReplay(replay).apply(world);
+        GameState::transition_now(world, GameState::Loading);
+        GameState::transition_now(world, GameState::Playing);
+        run_ticks(world, 600);
+        assert_eq!(snapshot(world), played);
+    }
+
+    fn round_seconds(world: &World) -> Vec<f32> {
+        let progress = world.resource::<MatchProgress>();
+        progress.rounds.iter().map(|r| r.seconds).collect()
+    }
+
+    #[test]
+    fn playback_plays_every_round() {
+        let config = GameConfig {
+            rounds: 3,
+            time_limit_seconds: Some(2),
+            ..config()
+        };
+        let mut game = HeadlessMatch::new(config.clone(), default());
+        let world = game.world_mut();
+        world.resource_mut::<GameSettings>().next_game = config;
+        GameState::transition_now(world, GameState::Loading);
+        GameState::transition_now(world, GameState::Playing);
+        run_ticks(world, 600);
+        let played = round_seconds(world);
+        assert!(played.len() >= 2);
+        let replay = world
+            .resource::<ReplayRecorder>()
+            .finish(world.resource::<SimulationTick>());
+        assert_eq!(replay.rounds.len(), played.len());
+
+        WatchReplay(replay).apply(world);
+        GameState::transition_now(world, GameState::Loading);
+        GameState::transition_now(world, GameState::Playing);
+        run_ticks(world, 600);
+        assert_eq!(round_seconds(world), played);
+    }
+}
@@ -1,50 +1,242 @@
 use crate::{
     ball::{Ball, BallAssets},
     boundaries::Boundaries,
+    controller::{ControllerKind, TeamControllers},
+    formation::facing_along,
+    game_state::GameState,
     gym::{Gym, GymAssets, GymParams},
+    match_progress::MatchProgress,
+    match_rng::MatchRng,
+    player::Jail,
+    replay::{Playback, ReplayRecorder},
     scoreboard::ScoreBoard,
     settings::{GameConfig, GameSettings},
-    squad::{AllSquadAssets, Squad, SquadBehaviors, SquadStates},
+    squad::{AllSquadAssets, Squad, SquadBehaviors, SquadCommands, SquadStates},
     team::{AllTeamAssets, Team},
+    tick::SimulationTick,
+    visuals::Visuals,
 };
 use bevy::{
     core_pipeline::bloom::BloomSettings,
     ecs::system::{Command, RunSystemOnce},
     prelude::*,
+    transform::systems::{propagate_transforms, sync_simple_transforms},
 };
 use bevy_mod_picking::prelude::RapierPickable;
+use bevy_rapier3d::prelude::{
+    CollisionEvent, ContactForceEvent, RapierConfiguration, RapierContext, TimestepMode,
+};
 use smooth_bevy_cameras::controllers::orbit::OrbitCameraBundle;
 
+/// Starts a new match by transitioning to `GameState::Loading`.
 pub struct RestartGame;
 
 impl Command for RestartGame {
     fn apply(self, world: &mut bevy::prelude::World) {
-        world.run_system_once(destroy_scene);
-        world.run_system_once(start_game);
+        world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Loading);
     }
 }
 
+/// Immediately replaces the scene with a new match, forgetting the rounds
+/// played so far.
+pub fn load_game(world: &mut World) {
+    world.insert_resource(MatchProgress::default());
+    world.insert_resource(SquadCommands::default());
+    respawn_scene(world);
+    let config = world.resource::<GameConfig>().clone();
+    world.resource_mut::<MatchProgress>().first_round = Some(config);
+}
+
+/// Starts the next round of the current match with a new seed.
+pub struct StartNextRound;
+
+impl Command for StartNextRound {
+    fn apply(self, world: &mut World) {
+        let mut config = world.resource::<GameConfig>().clone();
+        config.seed = config.seed.wrapping_add(1);
+        world.insert_resource(ConfigOverride(config));
+        // The replay covers the whole match.
+        let tick = *world.resource::<SimulationTick>();
+        let recorder = world.remove_resource::<ReplayRecorder>();
+        respawn_scene(world);
+        if let Some(mut recorder) = recorder {
+            recorder.next_round(&tick);
+            world.insert_resource(recorder);
+        }
+    }
+}
+
+/// Replaces the scene with a new one from `start_game`, which then plays out
+/// the same as the first scene of a fresh app.
+fn respawn_scene(world: &mut World) {
+    world.run_system_once(destroy_scene);
+    // Rapier's handles, islands and contacts would otherwise carry over.
+    *world.resource_mut::<RapierContext>() = default();
+    world.resource_mut::<Events<CollisionEvent>>().clear();
+    world.resource_mut::<Events<ContactForceEvent>>().clear();
+    world.run_system_once(start_game);
+    // The first tick reads `GlobalTransform`, which would otherwise only be
+    // updated at the end of the frame.
+    world.run_system_once(sync_simple_transforms);
+    world.run_system_once(propagate_transforms);
+}
+
+/// Used by the next `start_game` instead of `GameSettings::next_game`.
+#[derive(Resource)]
+pub struct ConfigOverride(pub GameConfig);
+
 fn destroy_scene(mut commands: Commands, all_scene: Query<Entity, With<GlobalTransform>>) {
     for entity in &all_scene {
         commands.entity(entity).despawn();
     }
 }
 
+/// Spawns a new game from a `ConfigOverride`, from the replay's config during
+/// `Playback`, or from `GameSettings::next_game`. During `Playback`, teams also
+/// get the replay's controllers instead of `TeamControllers`.
+///
+/// When there is no mesh or material storage, e.g. under `MinimalPlugins`,
+/// only the physical entities are spawned.
+#[allow(clippy::complexity)]
 pub fn start_game(
     mut commands: Commands,
     settings: Res<GameSettings>,
     gym_params: Res<GymParams>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    controllers: Res<TeamControllers>,
+    playback: Option<Res<Playback>>,
+    config_override: Option<Res<ConfigOverride>>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
 ) {
+    let mut visuals = Visuals::new(meshes, materials);
+    let config = if let Some(config_override) = config_override {
+        commands.remove_resource::<ConfigOverride>();
+        config_override.0.clone()
+    } else if let Some(playback) = &playback {
+        playback.replay.config.clone()
+    } else {
+        settings.next_game.clone()
+    };
+    let controllers = playback.map_or(controllers.kinds(), |p| p.replay.controllers);
+
     let he = gym_params.half_extents();
-    let gym_assets = GymAssets::new(*gym_params, &mut meshes, &mut materials);
+    let gym_assets = GymAssets::new(*gym_params, visuals.as_mut());
     Gym::spawn(&mut commands, &gym_assets);
     let bounds = Boundaries { min: -he, max: he };
     let player_spawn_aabbs = gym_params.player_spawn_aabbs();
     let ball_spawn_aabb = gym_params.ball_spawn_aabb();
     let occupancy = gym_params.occupancy_grid();
+    let ball_paths = gym_params.ball_paths();
+    let ball_grid = gym_params.ball_grid();
+    let jail_aabbs = gym_params.jail_aabbs();
+
+    if visuals.is_some() {
+        spawn_camera_and_lights(&mut commands, &settings, he);
+    }
+
+    let GameConfig { seed, n_balls, .. } = config;
+    let mut rng = MatchRng::new(seed);
+
+    // Rapier steps exactly once per tick.
+    let dt = config.tick_seconds();
+    fixed_time.set_timestep_seconds(f64::from(dt));
+    rapier_config.timestep_mode = TimestepMode::Fixed { dt, substeps: 1 };
+
+    let ball_assets = BallAssets::new(visuals.as_mut());
+    Ball::spawn_multiple_in_aabb(
+        &mut commands,
+        &ball_assets,
+        &bounds,
+        ball_spawn_aabb,
+        n_balls,
+        &mut rng,
+    );
+
+    let team_colors = [Color::GREEN, Color::BLUE];
+    let squad_teams: Vec<_> = (0..config.squads_of_team(1).end)
+        .map(|squad| config.team_of_squad(squad))
+        .collect();
+
+    let squad_colors: Vec<_> = squad_teams
+        .iter()
+        .map(|&t| team_colors[t as usize])
+        .collect();
+    let team_assets = AllTeamAssets::new(team_colors, visuals.as_mut());
+    let squad_assets = AllSquadAssets::new(squad_colors, visuals.as_mut());
+
+    let squad_points: Vec<_> = (0..2)
+        .flat_map(|team| {
+            let bot = controllers[team as usize] == ControllerKind::Bot;
+            let squads = config.team_size(team).squads;
+            config.point_buy.squad_points(team, squads, bot)
+        })
+        .collect();
+    let squad_sizes: Vec<_> = squad_points
+        .iter()
+        .zip(&squad_teams)
+        .map(|(points, &team)| points.squad_size(config.team_size(team).players_per_squad))
+        .collect();
+
+    let mut squad_ai_entities = Vec::new();
+    for team in 0..2 {
+        let squads = config.squads_of_team(team);
+        Squad::spawn_in_line(
+            &mut commands,
+            &team_assets.teams[team as usize],
+            &squad_assets,
+            Team::new(team, controllers[team as usize]),
+            squads.clone(),
+            player_spawn_aabbs[team as usize],
+            &squad_sizes[usize::from(squads.start)..usize::from(squads.end)],
+            &mut squad_ai_entities,
+            &mut rng,
+        );
+    }
+
+    let mut squad_behaviors = SquadBehaviors::new(squad_ai_entities);
+    let bot_aim_spread = config.bot_difficulty.params().aim_spread_factor;
+    // Formations start out facing the other team.
+    for ((behavior, &team), points) in squad_behaviors
+        .squads
+        .iter_mut()
+        .zip(&squad_teams)
+        .zip(&squad_points)
+    {
+        let toward_enemy = if team == 0 { -Vec2::Y } else { Vec2::Y };
+        behavior.facing = facing_along(toward_enemy);
+        behavior.stats = points.stats();
+        if controllers[team as usize] == ControllerKind::Bot {
+            behavior.stats.throw_spread *= bot_aim_spread;
+        }
+    }
+    let squad_states = SquadStates::new(squad_sizes);
+
+    commands.insert_resource(ball_assets);
+    commands.insert_resource(bounds);
+    commands.insert_resource(occupancy);
+    commands.insert_resource(ball_paths);
+    commands.insert_resource(ball_grid);
+    commands.insert_resource(rng);
+    if config.catch_in {
+        commands.insert_resource(Jail::new(jail_aabbs));
+    } else {
+        commands.remove_resource::<Jail>();
+    }
+    commands.insert_resource(ScoreBoard::new(2));
+    commands.insert_resource(ReplayRecorder::new(config.clone(), controllers));
+    commands.insert_resource(SimulationTick::default());
+    commands.insert_resource(config);
+    commands.insert_resource(squad_behaviors);
+    commands.insert_resource(squad_states);
+    commands.insert_resource(team_assets);
+    commands.insert_resource(squad_assets);
+}
 
+fn spawn_camera_and_lights(commands: &mut Commands, settings: &GameSettings, he: Vec3) {
     commands
         .spawn(Camera3dBundle {
             camera: Camera {
@@ -87,69 +279,4 @@ pub fn start_game(
             ..default()
         });
     }
-
-    let GameConfig {
-        squads_per_team,
-        players_per_squad,
-        n_balls,
-        ..
-    } = settings.next_game;
-
-    let ball_assets = BallAssets::new(&mut meshes, &mut materials);
-    Ball::spawn_multiple_in_aabb(
-        &mut commands,
-        &ball_assets,
-        &bounds,
-        ball_spawn_aabb,
-        n_balls,
-    );
-
-    let team_colors = [Color::GREEN, Color::BLUE];
-    let squad_teams: Vec<_> = std::iter::repeat(0u8)
-        .take(usize::from(squads_per_team))
-        .chain(std::iter::repeat(1).take(usize::from(squads_per_team)))
-        .collect();
-    let n_squads = squad_teams.len();
-
-    let squad_colors: Vec<_> = squad_teams
-        .iter()
-        .map(|&t| team_colors[t as usize])
-        .collect();
-    let team_assets = AllTeamAssets::new(team_colors, &mut meshes, &mut materials);
-    let squad_assets = AllSquadAssets::new(squad_colors, &mut materials);
-
-    let mut squad_ai_entities = Vec::new();
-    Squad::spawn_in_line(
-        &mut commands,
-        &team_assets.teams[0],
-        &squad_assets,
-        Team::new_human(0),
-        0..squads_per_team,
-        player_spawn_aabbs[0],
-        players_per_squad,
-        &mut squad_ai_entities,
-    );
-    Squad::spawn_in_line(
-        &mut commands,
-        &team_assets.teams[1],
-        &squad_assets,
-        Team::new_bot(1),
-        squads_per_team..2 * squads_per_team,
-        player_spawn_aabbs[1],
-        players_per_squad,
-        &mut squad_ai_entities,
-    );
-
-    let squad_behaviors = SquadBehaviors::new(squad_ai_entities);
-    let squad_states = SquadStates::new(vec![players_per_squad; n_squads]);
-
-    commands.insert_resource(ball_assets);
-    commands.insert_resource(bounds);
-    commands.insert_resource(occupancy);
-    commands.insert_resource(ScoreBoard::new(2));
-    commands.insert_resource(settings.next_game.clone());
-    commands.insert_resource(squad_behaviors);
-    commands.insert_resource(squad_states);
-    commands.insert_resource(team_assets);
-    commands.insert_resource(squad_assets);
 }
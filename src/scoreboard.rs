@@ -3,16 +3,31 @@ use bevy_egui::{egui, EguiContexts};
 
 #[derive(Resource)]
 pub struct ScoreBoard {
+    /// Players knocked out by each team's throws.
     pub team_scores: Vec<u32>,
+    /// Throwers knocked out by each team's catches.
+    pub team_catches: Vec<u32>,
+    /// Thrown balls blocked by each team.
+    pub team_blocks: Vec<u32>,
+    /// Held balls each team lost while blocking.
+    pub team_fumbles: Vec<u32>,
 }
 
 impl ScoreBoard {
     pub fn new(n_teams: u8) -> Self {
         Self {
             team_scores: vec![0; n_teams as usize],
+            team_catches: vec![0; n_teams as usize],
+            team_blocks: vec![0; n_teams as usize],
+            team_fumbles: vec![0; n_teams as usize],
         }
     }
 
+    /// All players knocked out by `team`, through hits or catches.
+    pub fn knockouts(&self, team: u8) -> u32 {
+        self.team_scores[team as usize] + self.team_catches[team as usize]
+    }
+
     pub fn draw(scoreboard: Res<Self>, mut contexts: EguiContexts) {
         egui::Window::new("Your Team")
             .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(0.0, 0.0))
@@ -21,7 +36,12 @@ impl ScoreBoard {
             .movable(false)
             .collapsible(false)
             .show(contexts.ctx_mut(), |ui| {
-                ui.label(format!("{}", scoreboard.team_scores[0]));
+                ui.label(format!(
+                    "{} hits, {} catches, {} blocks",
+                    scoreboard.team_scores[0],
+                    scoreboard.team_catches[0],
+                    scoreboard.team_blocks[0]
+                ));
             });
         egui::Window::new("The Bad Guys")
             .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(0.0, 0.0))
@@ -30,7 +50,12 @@ impl ScoreBoard {
             .movable(false)
             .collapsible(false)
             .show(contexts.ctx_mut(), |ui| {
-                ui.label(format!("{}", scoreboard.team_scores[1]));
+                ui.label(format!(
+                    "{} hits, {} catches, {} blocks",
+                    scoreboard.team_scores[1],
+                    scoreboard.team_catches[1],
+                    scoreboard.team_blocks[1]
+                ));
             });
     }
 }
@@ -1,11 +1,16 @@
+use crate::{opponent_ai::BotDifficulty, point_buy::PointBuy};
 use bevy::{app::AppExit, prelude::*};
 use bevy_pkv::PkvStore;
 use serde::{Deserialize, Serialize};
 use smooth_bevy_cameras::controllers::orbit::OrbitCameraController;
+use std::{ops::Range, time::Duration};
 
 #[derive(Clone, Deserialize, Resource, Serialize)]
 pub struct GameSettings {
     pub hide_menu_when_game_starts: bool,
+    /// Choose a new `GameConfig::seed` before every game.
+    #[serde(default = "default_randomize_seed")]
+    pub randomize_seed: bool,
     pub next_game: GameConfig,
     pub translate_sensitivity: f32,
     pub rotate_sensitivity: f32,
@@ -16,6 +21,7 @@ impl Default for GameSettings {
     fn default() -> Self {
         Self {
             hide_menu_when_game_starts: false,
+            randomize_seed: default_randomize_seed(),
             next_game: default(),
             translate_sensitivity: 1.0,
             rotate_sensitivity: 0.1,
@@ -29,6 +35,14 @@ impl GameSettings {
         if let Ok(loaded) = pkv.get::<Self>("settings") {
             *settings = loaded;
         }
+        settings.prepare_next_game();
+    }
+
+    /// Must be called before starting a new game.
+    pub fn prepare_next_game(&mut self) {
+        if self.randomize_seed {
+            self.next_game.seed = rand::random();
+        }
     }
 
     /// Must run in `Last` schedule.
@@ -64,22 +78,111 @@ impl GameSettings {
 #[derive(Clone, Deserialize, Resource, Serialize)]
 pub struct GameConfig {
     pub mode: GameMode,
+    /// Seeds the `MatchRng`. The same seed and inputs replay the same match.
+    #[serde(default)]
+    pub seed: u64,
+    /// Simulation ticks per second of game time.
+    #[serde(default = "default_ticks_per_second")]
+    pub ticks_per_second: u32,
     pub squads_per_team: u8,
     pub players_per_squad: u32,
     pub n_balls: u32,
+    /// In `GameMode::Match`, a round that runs this long is won by the team
+    /// with the most knockouts.
+    #[serde(default)]
+    pub time_limit_seconds: Option<u32>,
+    /// In `GameMode::Match`, the number of rounds in a best-of-N match.
+    #[serde(default = "default_rounds")]
+    pub rounds: u32,
+    /// Knocked out players wait in a sideline jail instead of leaving the
+    /// game, and each catch brings one back. Catches while nobody is out free
+    /// nobody.
+    #[serde(default)]
+    pub catch_in: bool,
+    /// Stats and squad sizes bought by each team.
+    #[serde(default)]
+    pub point_buy: PointBuy,
+    #[serde(default)]
+    pub bot_difficulty: BotDifficulty,
+    /// Replaces `squads_per_team` and `players_per_squad` for either team, for
+    /// uneven matches.
+    #[serde(default)]
+    pub team_sizes: [Option<TeamSize>; 2],
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct TeamSize {
+    pub squads: u8,
+    pub players_per_squad: u32,
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
         Self {
             mode: GameMode::Survival,
+            seed: 0,
+            ticks_per_second: default_ticks_per_second(),
             squads_per_team: 2,
             players_per_squad: 100,
             n_balls: 300,
+            time_limit_seconds: None,
+            rounds: default_rounds(),
+            catch_in: false,
+            point_buy: default(),
+            bot_difficulty: default(),
+            team_sizes: default(),
         }
     }
 }
 
+impl GameConfig {
+    pub fn tick_seconds(&self) -> f32 {
+        1.0 / self.ticks_per_second.max(1) as f32
+    }
+
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_secs_f32(self.tick_seconds())
+    }
+
+    pub fn team_size(&self, team: u8) -> TeamSize {
+        self.team_sizes[team as usize].unwrap_or(TeamSize {
+            squads: self.squads_per_team,
+            players_per_squad: self.players_per_squad,
+        })
+    }
+
+    /// Squads are numbered team by team.
+    pub fn squads_of_team(&self, team: u8) -> Range<u8> {
+        let first = if team == 0 {
+            0
+        } else {
+            self.team_size(0).squads
+        };
+        first..first + self.team_size(team).squads
+    }
+
+    pub fn team_of_squad(&self, squad: u8) -> u8 {
+        u8::from(squad >= self.team_size(0).squads.max(1))
+    }
+
+    /// The number of round wins needed to win a best-of-N match.
+    pub fn rounds_to_win(&self) -> u32 {
+        self.rounds.max(1) / 2 + 1
+    }
+}
+
+fn default_randomize_seed() -> bool {
+    true
+}
+
+fn default_ticks_per_second() -> u32 {
+    60
+}
+
+fn default_rounds() -> u32 {
+    1
+}
+
 #[derive(Clone, Copy, Eq, Deserialize, PartialEq, Serialize)]
 pub enum GameMode {
     Match,
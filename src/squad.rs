@@ -1,19 +1,29 @@
 use crate::{
     collision,
+    controller::ControllerKind,
+    formation::{slot_spacing, Formation, FormationSlot},
     geometry::Aabb2,
+    match_progress::MatchProgress,
     opponent_ai::Bot,
     parameters::{
-        BLOOM_INTENSITY, SQUAD_AI_COLLIDER_HEIGHT, SQUAD_AI_COLLIDER_RADIUS, SQUAD_CLUSTER_DENSITY,
+        BALL_LEASH, BLOOM_INTENSITY, CATCH_EASY_SPEED, CATCH_HOLDING_FACTOR,
+        LEADER_MIN_SPEED_FRACTION, LEADER_SPEED_FACTOR, SQUAD_AI_COLLIDER_HEIGHT,
+        SQUAD_AI_COLLIDER_RADIUS, SQUAD_CLUSTER_DENSITY, THROW_LOFT, THROW_LOFT_RANGE,
+        THROW_SPREAD_ANGLE, WAYPOINT_RADIUS,
     },
-    player::{KnockedOut, Player, PlayerBall, PlayerBundle},
+    player::{KnockedOut, Player, PlayerBall},
+    replay::{Playback, ReplayRecorder},
     settings::{GameConfig, GameMode},
     team::{AllTeamAssets, Team, TeamAssets},
+    tick::SimulationTick,
+    visuals::Visuals,
 };
 use bevy::prelude::*;
 use bevy_mod_picking::prelude::*;
 use bevy_rapier3d::prelude::{Collider, CollisionGroups, Group, QueryFilter, RapierContext};
 use rand::Rng;
-use std::ops::Range;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, ops::Range};
 
 #[derive(Clone, Copy, Component)]
 pub struct Squad {
@@ -35,22 +45,24 @@ impl Squad {
         aabb: Aabb2,
         leader_pos: Vec2,
         n_players: u32,
+        rng: &mut impl Rng,
     ) -> Entity {
         assert!(n_players > 0);
 
         let y = 0.5 * team_assets.size.y;
 
-        let mut rng = rand::thread_rng();
-        for _ in 0..n_players {
+        for slot in 0..n_players {
             let x = rng.gen_range(aabb.min.x..aabb.max.x);
             let z = rng.gen_range(aabb.min.y..aabb.max.y);
-            commands.spawn(PlayerBundle::new(
+            Player::spawn(
+                commands,
                 team_assets,
                 squad_assets,
                 team,
                 squad,
+                slot,
                 Vec3::new(x, y, z),
-            ));
+            );
         }
 
         let mut commands = commands.spawn(SquadAiBundle::new(
@@ -58,10 +70,14 @@ impl Squad {
             squad,
             Vec3::new(leader_pos.x, 0.0, leader_pos.y),
         ));
-        if team.is_human() {
-            commands.insert(SquadAiPickableBundle::new(squad));
-        } else {
-            commands.insert(Bot);
+        match team.controller() {
+            ControllerKind::Human => {
+                commands.insert(SquadAiPickableBundle::new(squad));
+            }
+            ControllerKind::Bot => {
+                commands.insert(Bot::default());
+            }
+            ControllerKind::Custom => {}
         }
 
         commands.id()
@@ -75,8 +91,9 @@ impl Squad {
         team: Team,
         squads: Range<u8>,
         aabb: Aabb2,
-        players_per_squad: u32,
+        squad_sizes: &[u32],
         squad_ai_entities: &mut Vec<Entity>,
+        rng: &mut impl Rng,
     ) {
         let n_squads = squads.len();
         let dx_squad = (aabb.max.x - aabb.min.x) / n_squads as f32;
@@ -93,7 +110,8 @@ impl Squad {
                 squad,
                 aabb,
                 leader_pos,
-                players_per_squad,
+                squad_sizes[i],
+                rng,
             )
         }));
     }
@@ -107,25 +125,26 @@ pub struct AllSquadAssets {
 impl AllSquadAssets {
     pub fn new(
         squad_colors: impl IntoIterator<Item = Color>,
-        materials: &mut Assets<StandardMaterial>,
+        mut visuals: Option<&mut Visuals>,
     ) -> Self {
         Self {
             squads: squad_colors
                 .into_iter()
-                .map(|color| SquadAssets::new(color, materials))
+                .map(|color| SquadAssets::new(color, visuals.as_deref_mut()))
                 .collect(),
         }
     }
 }
 
 pub struct SquadAssets {
-    pub in_play_material: Handle<StandardMaterial>,
+    /// `None` when running headless.
+    pub in_play_material: Option<Handle<StandardMaterial>>,
 }
 
 impl SquadAssets {
-    pub fn new(color: Color, materials: &mut Assets<StandardMaterial>) -> Self {
+    pub fn new(color: Color, visuals: Option<&mut Visuals>) -> Self {
         Self {
-            in_play_material: materials.add(color.into()),
+            in_play_material: visuals.map(|visuals| visuals.materials.add(color.into())),
         }
     }
 }
@@ -142,20 +161,314 @@ impl SquadBehaviors {
         }
     }
 
+    /// Holding shift adds a waypoint instead of replacing the squad's path.
     pub fn set_leader_position(
         event: Listener<Pointer<Click>>,
-        mut behaviors: ResMut<Self>,
+        key: Res<Input<KeyCode>>,
+        mut commands: ResMut<SquadCommands>,
         states: Res<SquadStates>,
+        playback: Option<Res<Playback>>,
     ) {
-        if event.button != PointerButton::Primary {
+        if event.button != PointerButton::Primary || playback.is_some() {
             return;
         }
         let (Some(selected_squad), Some(position)) = (states.selected, event.hit.position) else {
             return;
         };
 
-        let behavior = &mut behaviors.squads[selected_squad as usize];
-        behavior.leader_position = Some(position.xz());
+        let squad = selected_squad;
+        let position = position.xz().to_array();
+        if key.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            commands.queue(SquadCommand::AddWaypoint { squad, position });
+        } else {
+            commands.queue(SquadCommand::SetLeaderPosition { squad, position });
+        }
+    }
+
+    pub fn toggle_patrol(
+        key: Res<Input<KeyCode>>,
+        mut commands: ResMut<SquadCommands>,
+        behaviors: Res<Self>,
+        states: Res<SquadStates>,
+        playback: Option<Res<Playback>>,
+    ) {
+        if !key.just_pressed(KeyCode::L) || playback.is_some() {
+            return;
+        }
+        let Some(squad) = states.selected else {
+            return;
+        };
+        commands.queue(SquadCommand::SetPatrol {
+            squad,
+            patrol: !behaviors.squads[squad as usize].patrol,
+        });
+    }
+
+    pub fn toggle_charge(
+        key: Res<Input<KeyCode>>,
+        mut commands: ResMut<SquadCommands>,
+        behaviors: Res<Self>,
+        states: Res<SquadStates>,
+        playback: Option<Res<Playback>>,
+    ) {
+        if !key.just_pressed(KeyCode::C) || playback.is_some() {
+            return;
+        }
+        let Some(squad) = states.selected else {
+            return;
+        };
+        commands.queue(SquadCommand::SetKnobs {
+            squad,
+            knobs: SquadKnobs {
+                charge: Some(!behaviors.squads[squad as usize].charge),
+                ..default()
+            },
+        });
+    }
+
+    pub fn volley(
+        key: Res<Input<KeyCode>>,
+        mut commands: ResMut<SquadCommands>,
+        behaviors: Res<Self>,
+        states: Res<SquadStates>,
+        playback: Option<Res<Playback>>,
+    ) {
+        if !key.just_pressed(KeyCode::V) || playback.is_some() {
+            return;
+        }
+        let Some(squad) = states.selected else {
+            return;
+        };
+        if behaviors.squads[squad as usize].auto_throw {
+            return;
+        }
+        commands.queue(SquadCommand::Volley { squad });
+    }
+
+    /// Moves on to the next waypoint once a squad reaches its leader position.
+    pub fn advance_waypoints(mut behaviors: ResMut<Self>, states: Res<SquadStates>) {
+        for (behavior, state) in behaviors.squads.iter_mut().zip(&states.squads) {
+            let Some(leader_pos) = behavior.leader_position else {
+                continue;
+            };
+            if state.num_players == 0
+                || state.center_of_mass.xz().distance(leader_pos) > WAYPOINT_RADIUS
+            {
+                continue;
+            }
+            let Some(next) = behavior.waypoints.pop_front() else {
+                continue;
+            };
+            if behavior.patrol {
+                behavior.waypoints.push_back(leader_pos);
+            }
+            behavior.leader_position = Some(next);
+        }
+    }
+}
+
+/// An order given to a squad from outside of the simulation.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum SquadCommand {
+    Select {
+        squad: u8,
+    },
+    SetLeaderPosition {
+        squad: u8,
+        position: [f32; 2],
+    },
+    /// Appends to the squad's path.
+    AddWaypoint {
+        squad: u8,
+        position: [f32; 2],
+    },
+    /// Loop back to the start of the path after reaching the end.
+    SetPatrol {
+        squad: u8,
+        patrol: bool,
+    },
+    SetKnobs {
+        squad: u8,
+        knobs: SquadKnobs,
+    },
+    /// Throw together once enough players are ready. Only used when
+    /// `SquadBehavior::auto_throw` is off.
+    Volley {
+        squad: u8,
+    },
+}
+
+impl SquadCommand {
+    /// Whether the command refers to one of `n_squads` squads and has sensible
+    /// values. Commands from replay files and controllers can't be trusted.
+    pub fn is_valid(&self, n_squads: usize) -> bool {
+        let (squad, valid_values) = match *self {
+            Self::Select { squad } | Self::SetPatrol { squad, .. } | Self::Volley { squad } => {
+                (squad, true)
+            }
+            Self::SetLeaderPosition { squad, position } | Self::AddWaypoint { squad, position } => {
+                (squad, Vec2::from_array(position).is_finite())
+            }
+            Self::SetKnobs { squad, knobs } => (squad, knobs.is_valid()),
+        };
+        usize::from(squad) < n_squads && valid_values
+    }
+
+    fn apply(self, behaviors: &mut SquadBehaviors, states: &mut SquadStates) {
+        match self {
+            Self::Select { squad } => {
+                states.selected = Some(squad);
+            }
+            Self::SetLeaderPosition { squad, position } => {
+                behaviors.squads[squad as usize].go_to(Vec2::from_array(position));
+            }
+            Self::AddWaypoint { squad, position } => {
+                behaviors.squads[squad as usize].add_waypoint(Vec2::from_array(position));
+            }
+            Self::SetPatrol { squad, patrol } => {
+                behaviors.squads[squad as usize].patrol = patrol;
+            }
+            Self::SetKnobs { squad, knobs } => {
+                knobs.apply(&mut behaviors.squads[squad as usize]);
+            }
+            Self::Volley { squad } => {
+                states.squads[squad as usize].volley_requested = true;
+            }
+        }
+    }
+}
+
+/// Changes to the tunable parts of a `SquadBehavior`. Fields that are `None`
+/// are left alone.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SquadKnobs {
+    pub cluster_density: Option<f32>,
+    pub throw_rate: Option<f32>,
+    pub throw_min_balls: Option<u32>,
+    pub throw_loft: Option<f32>,
+    pub auto_throw: Option<bool>,
+    pub charge: Option<bool>,
+    pub ball_leash: Option<f32>,
+    pub formation: Option<Formation>,
+    pub facing: Option<f32>,
+}
+
+impl SquadKnobs {
+    /// Rejects values that aren't finite, and densities and rates that aren't
+    /// positive. Other values are clamped when applied.
+    pub fn is_valid(&self) -> bool {
+        let finite = |value: Option<f32>| value.is_none_or(f32::is_finite);
+        let positive = |value: Option<f32>| value.is_none_or(|v| v.is_finite() && v > 0.0);
+        positive(self.cluster_density)
+            && positive(self.throw_rate)
+            && finite(self.throw_loft)
+            && finite(self.ball_leash)
+            && finite(self.facing)
+    }
+
+    fn apply(self, behavior: &mut SquadBehavior) {
+        let Self {
+            cluster_density,
+            throw_rate,
+            throw_min_balls,
+            throw_loft,
+            auto_throw,
+            charge,
+            ball_leash,
+            formation,
+            facing,
+        } = self;
+        if let Some(cluster_density) = cluster_density {
+            behavior.cluster_density = cluster_density;
+        }
+        if let Some(throw_rate) = throw_rate {
+            behavior.throw_rate = throw_rate;
+        }
+        if let Some(throw_min_balls) = throw_min_balls {
+            behavior.throw_min_balls = throw_min_balls;
+        }
+        if let Some(throw_loft) = throw_loft {
+            behavior.throw_loft =
+                throw_loft.clamp(*THROW_LOFT_RANGE.start(), *THROW_LOFT_RANGE.end());
+        }
+        if let Some(auto_throw) = auto_throw {
+            behavior.auto_throw = auto_throw;
+        }
+        if let Some(charge) = charge {
+            behavior.charge = charge;
+        }
+        if let Some(ball_leash) = ball_leash {
+            behavior.ball_leash = ball_leash.max(0.0);
+        }
+        if let Some(formation) = formation {
+            behavior.formation = formation;
+        }
+        if let Some(facing) = facing {
+            behavior.facing = facing;
+        }
+    }
+}
+
+/// Squad commands waiting for the next simulation tick.
+///
+/// Commands only take effect at the start of a tick, so they can be recorded
+/// and replayed exactly.
+#[derive(Default, Resource)]
+pub struct SquadCommands {
+    queued: Vec<SquadCommand>,
+}
+
+impl SquadCommands {
+    pub fn queue(&mut self, command: SquadCommand) {
+        if let SquadCommand::SetLeaderPosition { squad, .. } = command {
+            // Only the latest path matters, e.g. after several clicks while
+            // paused.
+            self.queued.retain(|c| {
+                !matches!(
+                    c,
+                    SquadCommand::SetLeaderPosition { squad: s, .. }
+                        | SquadCommand::AddWaypoint { squad: s, .. }
+                        if *s == squad
+                )
+            });
+        }
+        self.queued.push(command);
+    }
+
+    /// Commands that haven't been applied yet, e.g. because the game is
+    /// paused.
+    pub fn queued(&self) -> &[SquadCommand] {
+        &self.queued
+    }
+
+    /// Must run at the start of each tick.
+    pub fn apply(
+        tick: Res<SimulationTick>,
+        progress: Res<MatchProgress>,
+        playback: Option<Res<Playback>>,
+        mut commands: ResMut<Self>,
+        mut recorder: ResMut<ReplayRecorder>,
+        mut behaviors: ResMut<SquadBehaviors>,
+        mut states: ResMut<SquadStates>,
+    ) {
+        let to_apply: Vec<_> = if let Some(playback) = playback {
+            commands.queued.clear();
+            playback
+                .replay
+                .commands_at(progress.rounds.len(), tick.0)
+                .to_vec()
+        } else {
+            commands.queued.drain(..).collect()
+        };
+
+        for command in to_apply {
+            if !command.is_valid(behaviors.squads.len()) {
+                warn!("Ignoring invalid command {command:?}");
+                continue;
+            }
+            command.apply(&mut behaviors, &mut states);
+            recorder.record(tick.0, command);
+        }
     }
 }
 
@@ -168,17 +481,33 @@ pub struct SquadBehavior {
     /// A manually configured leader position. If None, leader will decide where
     /// to go.
     pub leader_position: Option<Vec2>,
+    /// Where to go after reaching `leader_position`, in order.
+    pub waypoints: VecDeque<Vec2>,
+    /// Reached waypoints go to the back of the path, so the squad keeps
+    /// looping.
+    pub patrol: bool,
     /// Players per square meter.
     pub cluster_density: f32,
     /// Balls per second.
     pub throw_rate: f32,
-    /// Minimum number of balls to throw at a time.
+    /// Minimum number of balls to throw at a time. The squad holds its fire
+    /// until this many players are in range.
     pub throw_min_balls: u32,
-    /// How much Y velocity (loft) a throw should have.
-    pub throw_y_vel: f32,
+    /// How far above the thrower's head a thrown ball rises. High lobs clear
+    /// friendly clusters, while flat throws arrive sooner.
+    ///
+    /// This should be nonzero to avoid friendly fire.
+    pub throw_loft: f32,
     /// When true, player AIs will decide when to throw. When false, the human
     /// player must provide input to make then throw.
     pub auto_throw: bool,
+    /// The leader token moves at full speed without waiting for stragglers.
+    pub charge: bool,
+    /// How far outside the cluster players will run to grab a loose ball.
+    pub ball_leash: f32,
+    pub formation: Formation,
+    /// The angle of the formation's forward direction on the XZ plane.
+    pub facing: f32,
     pub stats: PlayerStats,
 }
 
@@ -187,19 +516,55 @@ impl SquadBehavior {
         Self {
             leader,
             leader_position: None,
+            waypoints: VecDeque::new(),
+            patrol: false,
             cluster_density: SQUAD_CLUSTER_DENSITY,
             throw_rate: 1.0,
             throw_min_balls: 1,
-            throw_y_vel: 4.0,
+            throw_loft: THROW_LOFT,
             auto_throw: true,
+            charge: false,
+            ball_leash: BALL_LEASH,
+            formation: default(),
+            facing: 0.0,
             stats: default(),
         }
     }
+
+    /// Replaces the squad's path with a single position.
+    pub fn go_to(&mut self, position: Vec2) {
+        self.leader_position = Some(position);
+        self.waypoints.clear();
+    }
+
+    pub fn add_waypoint(&mut self, position: Vec2) {
+        if self.leader_position.is_none() {
+            self.leader_position = Some(position);
+        } else {
+            self.waypoints.push_back(position);
+        }
+    }
+
+    /// The leader position followed by the remaining waypoints.
+    pub fn path(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.leader_position
+            .into_iter()
+            .chain(self.waypoints.iter().copied())
+    }
 }
 
+#[derive(Clone, Copy, Debug)]
 pub struct PlayerStats {
     pub run_speed: f32,
     pub throw_distance: f32,
+    /// The largest angle in radians that a throw can stray from its target.
+    pub throw_spread: f32,
+    /// Probability of catching a slow ball with empty hands.
+    pub catch_skill: f32,
+    /// Probability of blocking a thrown ball with a held ball.
+    pub block_skill: f32,
+    /// How hard players try to sidestep incoming balls, from 0 to 1.
+    pub dodge: f32,
 }
 
 impl Default for PlayerStats {
@@ -207,10 +572,24 @@ impl Default for PlayerStats {
         Self {
             run_speed: 10.0,
             throw_distance: 15.0,
+            throw_spread: THROW_SPREAD_ANGLE,
+            catch_skill: 0.3,
+            block_skill: 0.4,
+            dodge: 0.5,
         }
     }
 }
 
+impl PlayerStats {
+    pub fn catch_probability(&self, holding_ball: bool, ball_speed: f32) -> f32 {
+        let mut p = self.catch_skill * (CATCH_EASY_SPEED / ball_speed.max(f32::EPSILON)).min(1.0);
+        if holding_ball {
+            p *= CATCH_HOLDING_FACTOR;
+        }
+        p.clamp(0.0, 1.0)
+    }
+}
+
 #[derive(Resource)]
 pub struct SquadStates {
     pub selected: Option<u8>,
@@ -233,11 +612,11 @@ impl SquadStates {
         behaviors: Res<SquadBehaviors>,
         mut squad_ai_colliders: Query<&mut Collider, With<SquadAi>>,
         squad_ais: Query<&GlobalTransform, With<SquadAi>>,
-        players: Query<
-            (&Squad, &GlobalTransform, &PlayerBall),
+        mut players: Query<
+            (&Squad, &GlobalTransform, &PlayerBall, &mut FormationSlot),
             (With<Player>, Without<KnockedOut>),
         >,
-        human_squad_ais: Query<(), (With<SquadAi>, Without<Bot>)>,
+        non_bot_squad_ais: Query<(), (With<SquadAi>, Without<Bot>)>,
     ) {
         for state in &mut states.squads {
             // Reset counters that we use below.
@@ -248,7 +627,7 @@ impl SquadStates {
         }
 
         // Squad accounting.
-        for (squad, tfm, player_ball) in &players {
+        for (squad, tfm, player_ball, _) in &players {
             let state = &mut states.squads[squad.squad as usize];
             state.num_players += 1;
             if player_ball.holding_ball {
@@ -273,6 +652,21 @@ impl SquadStates {
             }
         }
 
+        // Close the gaps left by knocked out players without reordering
+        // anyone, so the formation doesn't reshuffle. Ball carriers take the
+        // lowest slots, which are in front.
+        let mut slots: Vec<_> = players
+            .iter_mut()
+            .map(|(squad, _, player_ball, slot)| (squad.squad, !player_ball.holding_ball, slot))
+            .collect();
+        slots.sort_by_key(|(squad, empty_handed, slot)| (*squad, *empty_handed, slot.index));
+        let mut next_index = vec![0; states.squads.len()];
+        for (squad, _, mut slot) in slots {
+            let index = &mut next_index[squad as usize];
+            slot.index = *index;
+            *index += 1;
+        }
+
         for ((squad, state), behavior) in (0..).zip(&mut states.squads).zip(&behaviors.squads) {
             if state.num_players == 0 {
                 // Despawn leaders of empty squads.
@@ -283,7 +677,7 @@ impl SquadStates {
                         //
                         // It's a hack because I'd much rather have consistent
                         // entity lifetimes across all game modes.
-                        if let Ok(()) = human_squad_ais.get(behavior.leader) {
+                        if let Ok(()) = non_bot_squad_ais.get(behavior.leader) {
                             if let Some(ent_commands) = commands.get_entity(behavior.leader) {
                                 ent_commands.despawn_recursive();
                             }
@@ -298,7 +692,7 @@ impl SquadStates {
                 continue;
             }
 
-            state.set_cluster_radius(behavior.cluster_density);
+            state.set_cluster_radius(behavior);
 
             // Update squad AI colliders.
             let Ok(mut collider) = squad_ai_colliders.get_mut(behavior.leader) else {
@@ -317,6 +711,12 @@ pub struct SquadState {
     pub center_of_mass: Vec3,
     pub cluster_radius: f32,
     pub throw_target: Option<Vec3>,
+    /// Ball holders who are done cooling down and in range of the throw target.
+    pub num_ready_to_throw: u32,
+    /// The human called a volley that hasn't been thrown yet.
+    pub volley_requested: bool,
+    /// Throws allowed by `SquadBehavior::throw_rate` that haven't been used.
+    throw_credit: f32,
 }
 
 impl SquadState {
@@ -335,7 +735,48 @@ impl SquadState {
         (100 * self.num_players_in_cluster) / self.num_players.max(1)
     }
 
-    fn set_cluster_radius(&mut self, density: f32) {
+    /// How many of the `num_ready` players may throw this tick.
+    ///
+    /// With `auto_throw`, throws are limited to `throw_rate` per second and go
+    /// in groups of at least `throw_min_balls`. Otherwise everyone who is ready
+    /// throws at once when the human calls a volley.
+    pub fn schedule_throws(&mut self, behavior: &SquadBehavior, num_ready: u32, dt: f32) -> u32 {
+        self.num_ready_to_throw = num_ready;
+
+        // Allow bursts of up to a second's worth of throws, but always enough
+        // for the minimum.
+        let min_balls = behavior.throw_min_balls.max(1);
+        let max_credit = behavior.throw_rate.max(min_balls as f32);
+        self.throw_credit = (self.throw_credit + behavior.throw_rate * dt).min(max_credit);
+
+        if behavior.auto_throw {
+            self.volley_requested = false;
+        }
+        if num_ready < min_balls {
+            return 0;
+        }
+        if !behavior.auto_throw {
+            if !std::mem::take(&mut self.volley_requested) {
+                return 0;
+            }
+            return num_ready;
+        }
+        if self.throw_credit < min_balls as f32 {
+            return 0;
+        }
+        let n_throws = num_ready.min(self.throw_credit as u32);
+        self.throw_credit -= n_throws as f32;
+        n_throws
+    }
+
+    fn set_cluster_radius(&mut self, behavior: &SquadBehavior) {
+        let density = behavior.cluster_density;
+        let spacing = slot_spacing(density);
+        if let Some(radius) = behavior.formation.radius(self.num_players, spacing) {
+            self.cluster_radius = radius + 0.5 * spacing;
+            return;
+        }
+
         // density = players / area
         // area = players / density
         // radius^2 = (players / density) / PI
@@ -349,12 +790,14 @@ pub struct SquadAi;
 impl SquadAi {
     pub fn select_squad(
         event: Listener<Pointer<Click>>,
+        mut commands: ResMut<SquadCommands>,
         mut states: ResMut<SquadStates>,
+        playback: Option<Res<Playback>>,
         all_squad_assets: Res<AllSquadAssets>,
         mut materials: ResMut<Assets<StandardMaterial>>,
         squad_ais: Query<&Squad, With<Self>>,
     ) {
-        if event.button != PointerButton::Primary {
+        if event.button != PointerButton::Primary || playback.is_some() {
             return;
         }
         let Ok(selected_squad) = squad_ais.get(event.target) else {
@@ -363,12 +806,21 @@ impl SquadAi {
         if let Some(old_selected) = states.selected {
             if old_selected != selected_squad.squad {
                 let squad_assets = &all_squad_assets.squads[old_selected as usize];
-                if let Some(material) = materials.get_mut(&squad_assets.in_play_material) {
+                if let Some(material) = squad_assets
+                    .in_play_material
+                    .as_ref()
+                    .and_then(|handle| materials.get_mut(handle))
+                {
                     material.emissive = Color::BLACK;
                 }
             }
         }
+        // Select immediately so the UI stays responsive while paused. The
+        // command is still queued so it ends up in the replay.
         states.selected = Some(selected_squad.squad);
+        commands.queue(SquadCommand::Select {
+            squad: selected_squad.squad,
+        });
     }
 
     pub fn highlight_squad(
@@ -384,7 +836,11 @@ impl SquadAi {
 
         let team_assets = &all_team_assets.teams[team.team() as usize];
         let squad_assets = &all_squad_assets.squads[squad.squad as usize];
-        let Some(material) = materials.get_mut(&squad_assets.in_play_material) else {
+        let Some(material) = squad_assets
+            .in_play_material
+            .as_ref()
+            .and_then(|handle| materials.get_mut(handle))
+        else {
             return;
         };
 
@@ -408,22 +864,46 @@ impl SquadAi {
         }
 
         let squad_assets = &all_squad_assets.squads[squad.squad as usize];
-        let Some(material) = materials.get_mut(&squad_assets.in_play_material) else {
+        let Some(material) = squad_assets
+            .in_play_material
+            .as_ref()
+            .and_then(|handle| materials.get_mut(handle))
+        else {
             return;
         };
 
         material.emissive = Color::BLACK;
     }
 
+    /// Moves leader tokens towards their requested positions.
+    ///
+    /// Tokens slow down while the squad is spread out so that stragglers can
+    /// catch up, unless the squad is charging.
     pub fn move_to_requested_positions(
+        config: Res<GameConfig>,
         behaviors: Res<SquadBehaviors>,
+        states: Res<SquadStates>,
         mut squad_ais: Query<(&Squad, &mut Transform), With<Self>>,
     ) {
         for (squad, mut tfm) in &mut squad_ais {
             let behavior = &behaviors.squads[squad.squad as usize];
-            if let Some(requested_pos) = behavior.leader_position {
-                tfm.translation = Vec3::new(requested_pos.x, 0.0, requested_pos.y);
-            }
+            let Some(requested_pos) = behavior.leader_position else {
+                continue;
+            };
+            let state = &states.squads[squad.squad as usize];
+
+            let speed = if behavior.charge {
+                behavior.stats.run_speed
+            } else {
+                let cohesion = state.cluster_percent() as f32 / 100.0;
+                LEADER_SPEED_FACTOR
+                    * behavior.stats.run_speed
+                    * cohesion.max(LEADER_MIN_SPEED_FRACTION)
+            };
+            let requested_pos = Vec3::new(requested_pos.x, 0.0, requested_pos.y);
+            let step =
+                (requested_pos - tfm.translation).clamp_length_max(speed * config.tick_seconds());
+            tfm.translation += step;
         }
     }
 
@@ -1,6 +1,13 @@
 use crate::{
-    squad::{Squad, SquadAi, SquadStates},
-    team::Team,
+    formation::Formation,
+    parameters::{SQUAD_AI_COLLIDER_RADIUS, THROW_LOFT_RANGE},
+    player::throw_arc,
+    replay::Playback,
+    squad::{
+        Squad, SquadAi, SquadBehavior, SquadBehaviors, SquadCommand, SquadCommands, SquadKnobs,
+        SquadStates,
+    },
+    team::{AllTeamAssets, Team},
 };
 use bevy::prelude::*;
 use bevy_egui::{
@@ -20,12 +27,16 @@ impl SquadUi {
         }
     }
 
+    #[allow(clippy::complexity)]
     pub fn draw(
         ui: Res<Self>,
         mut gizmos: Gizmos,
         mut giz_config: ResMut<GizmoConfig>,
         mut egui: EguiContexts,
         squad_states: Res<SquadStates>,
+        squad_behaviors: Res<SquadBehaviors>,
+        squad_commands: Res<SquadCommands>,
+        team_assets: Res<AllTeamAssets>,
         cameras: Query<(&Camera, &GlobalTransform)>,
         squad_ais: Query<(&Team, &Squad, &GlobalTransform), With<SquadAi>>,
     ) {
@@ -42,6 +53,21 @@ impl SquadUi {
         let transparent_white = Color32::from_rgba_unmultiplied(255, 255, 255, 64);
         let stroke = egui::Stroke::new(3.0, transparent_white);
 
+        // Leader placements that will take effect on the next tick.
+        for command in squad_commands.queued() {
+            if let SquadCommand::SetLeaderPosition { position, .. }
+            | SquadCommand::AddWaypoint { position, .. } = *command
+            {
+                let position = Vec2::from_array(position);
+                gizmos.circle(
+                    Vec3::new(position.x, 0.0, position.y),
+                    Vec3::Y,
+                    SQUAD_AI_COLLIDER_RADIUS,
+                    Color::WHITE,
+                );
+            }
+        }
+
         for (team, squad, tfm) in &squad_ais {
             let to_egui_pos = |v: Vec2| egui::pos2(v.x, v.y);
             let dbg_painter = ctx.layer_painter(egui::LayerId::background());
@@ -57,6 +83,24 @@ impl SquadUi {
                 continue;
             }
 
+            if team.is_human() || ui.show_debug {
+                let behavior = &squad_behaviors.squads[squad.squad as usize];
+                draw_path(&mut gizmos, behavior, ai_pos);
+
+                if let Some(throw_target) = state.throw_target {
+                    let player_height = team_assets.teams[team.team() as usize].size.y;
+                    gizmos.linestrip(
+                        throw_arc(
+                            player_height,
+                            state.center_of_mass,
+                            throw_target,
+                            behavior.throw_loft,
+                        ),
+                        Color::YELLOW,
+                    );
+                }
+            }
+
             if team.is_human() {
                 gizmos.line(state.center_of_mass, tfm.translation(), Color::WHITE);
                 dbg_painter.circle(
@@ -78,13 +122,17 @@ impl SquadUi {
                 Color::rgb_u8(255, 255, 255),
             );
 
+            let behavior = &squad_behaviors.squads[squad.squad as usize];
             let text = format!(
-                "SQUAD {}\nplayers: {}\nballs: {}% ({})\ncluster: {}%",
+                "SQUAD {}\nplayers: {}\nballs: {}% ({})\nready: {}\ncluster: {}%\ncharge: {}\nformation: {}",
                 squad.squad,
                 state.num_players,
                 state.ball_percent(),
                 state.num_holding_balls,
-                state.cluster_percent()
+                state.num_ready_to_throw,
+                state.cluster_percent(),
+                behavior.charge,
+                behavior.formation.name()
             );
             let alignment = egui::Align2::LEFT_TOP;
             dbg_painter.debug_text(
@@ -97,4 +145,162 @@ impl SquadUi {
             );
         }
     }
+
+    /// Shows the selected squad's formation, throwing and ball chasing controls.
+    ///
+    /// Changes are queued like any other squad command, so they are recorded
+    /// in replays and take effect on the next tick.
+    pub fn edit_behavior(
+        mut egui: EguiContexts,
+        mut squad_commands: ResMut<SquadCommands>,
+        squad_behaviors: Res<SquadBehaviors>,
+        squad_states: Res<SquadStates>,
+        playback: Option<Res<Playback>>,
+    ) {
+        if playback.is_some() {
+            return;
+        }
+        let Some(squad) = squad_states.selected else {
+            return;
+        };
+
+        // Show what will happen on the next tick, e.g. while paused.
+        let behavior = &squad_behaviors.squads[squad as usize];
+        let mut formation = behavior.formation;
+        let mut facing = behavior.facing;
+        let mut throw_loft = behavior.throw_loft;
+        let mut throw_rate = behavior.throw_rate;
+        let mut throw_min_balls = behavior.throw_min_balls;
+        let mut auto_throw = behavior.auto_throw;
+        let mut ball_leash = behavior.ball_leash;
+        let mut volley_requested = squad_states.squads[squad as usize].volley_requested;
+        for command in squad_commands.queued() {
+            match *command {
+                SquadCommand::SetKnobs { squad: s, knobs } if s == squad => {
+                    formation = knobs.formation.unwrap_or(formation);
+                    facing = knobs.facing.unwrap_or(facing);
+                    throw_loft = knobs.throw_loft.unwrap_or(throw_loft);
+                    throw_rate = knobs.throw_rate.unwrap_or(throw_rate);
+                    throw_min_balls = knobs.throw_min_balls.unwrap_or(throw_min_balls);
+                    auto_throw = knobs.auto_throw.unwrap_or(auto_throw);
+                    ball_leash = knobs.ball_leash.unwrap_or(ball_leash);
+                }
+                SquadCommand::Volley { squad: s } if s == squad => {
+                    volley_requested = true;
+                }
+                _ => {}
+            }
+        }
+        let num_ready = squad_states.squads[squad as usize].num_ready_to_throw;
+
+        let mut knobs = SquadKnobs::default();
+        let mut volley = false;
+        egui::Window::new("Squad")
+            .anchor(egui::Align2::LEFT_TOP, egui::vec2(0.0, 0.0))
+            .resizable(false)
+            .movable(false)
+            .collapsible(false)
+            .show(egui.ctx_mut(), |ui| {
+                egui::ComboBox::from_id_source("formation")
+                    .selected_text(formation.name())
+                    .show_ui(ui, |ui| {
+                        for option in Formation::ALL {
+                            if ui
+                                .selectable_label(formation == option, option.name())
+                                .clicked()
+                            {
+                                knobs.formation = Some(option);
+                            }
+                        }
+                    });
+                let mut degrees = facing.to_degrees();
+                if ui
+                    .add(egui::Slider::new(&mut degrees, -180.0..=180.0).text("Facing"))
+                    .changed()
+                {
+                    knobs.facing = Some(degrees.to_radians());
+                }
+                if ui
+                    .add(egui::Slider::new(&mut throw_loft, THROW_LOFT_RANGE).text("Throw Loft"))
+                    .changed()
+                {
+                    knobs.throw_loft = Some(throw_loft);
+                }
+                if ui
+                    .add(egui::Slider::new(&mut ball_leash, 0.0..=20.0).text("Ball Leash"))
+                    .changed()
+                {
+                    knobs.ball_leash = Some(ball_leash);
+                }
+                ui.separator();
+
+                if ui.checkbox(&mut auto_throw, "Auto Throw").changed() {
+                    knobs.auto_throw = Some(auto_throw);
+                }
+                if ui
+                    .add_enabled(
+                        auto_throw,
+                        egui::Slider::new(&mut throw_rate, 0.5..=50.0).text("Balls Per Second"),
+                    )
+                    .changed()
+                {
+                    knobs.throw_rate = Some(throw_rate);
+                }
+                if ui
+                    .add(egui::Slider::new(&mut throw_min_balls, 1..=100).text("Min Balls"))
+                    .changed()
+                {
+                    knobs.throw_min_balls = Some(throw_min_balls);
+                }
+
+                let ready = num_ready >= throw_min_balls;
+                let color = if ready { Color32::GREEN } else { Color32::GRAY };
+                ui.colored_label(
+                    color,
+                    format!("{num_ready} / {throw_min_balls} ready to throw"),
+                );
+                if !auto_throw {
+                    let text = if volley_requested {
+                        "Volley Called"
+                    } else {
+                        "Volley (V)"
+                    };
+                    if ui
+                        .add_enabled(!volley_requested, egui::Button::new(text))
+                        .clicked()
+                    {
+                        volley = true;
+                    }
+                }
+            });
+
+        if knobs != SquadKnobs::default() {
+            squad_commands.queue(SquadCommand::SetKnobs { squad, knobs });
+        }
+        if volley {
+            squad_commands.queue(SquadCommand::Volley { squad });
+        }
+    }
+}
+
+/// Draws the rest of the path, starting from where the leader token is now.
+fn draw_path(gizmos: &mut Gizmos, behavior: &SquadBehavior, leader_pos: Vec3) {
+    let to_3d = |p: Vec2| Vec3::new(p.x, 0.0, p.y);
+    gizmos.linestrip(
+        std::iter::once(leader_pos).chain(behavior.path().map(to_3d)),
+        Color::WHITE,
+    );
+    if behavior.patrol {
+        if let (Some(first), Some(&last)) = (behavior.leader_position, behavior.waypoints.back()) {
+            gizmos.line(to_3d(last), to_3d(first), Color::WHITE);
+        }
+    }
+    for &waypoint in &behavior.waypoints {
+        gizmos.circle(
+            to_3d(waypoint),
+            Vec3::Y,
+            0.5 * SQUAD_AI_COLLIDER_RADIUS,
+            Color::WHITE,
+        );
+    }
 }
@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+/// The number of simulation ticks since the current game started.
+#[derive(Clone, Copy, Default, Resource)]
+pub struct SimulationTick(pub u64);
+
+impl SimulationTick {
+    /// Must run at the end of each tick.
+    pub fn advance(mut tick: ResMut<Self>) {
+        tick.0 += 1;
+    }
+}
+
+/// Immediately runs `n` simulation ticks, no matter how much time has passed.
+pub fn run_ticks(world: &mut World, n: u64) {
+    let timestep = world.resource::<Time<Fixed>>().timestep();
+    for _ in 0..n {
+        world.resource_mut::<Time<Fixed>>().advance_by(timestep);
+        *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+        world.run_schedule(FixedUpdate);
+    }
+    *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+}
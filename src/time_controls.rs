@@ -0,0 +1,76 @@
+use crate::{game_state::GameState, settings::GameConfig};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+/// The game speeds that can be chosen with the keyboard.
+pub const SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+/// Slow motion and fast-forward.
+///
+/// This scales the real length of a `Time<Fixed>` step, which changes how many
+/// simulation ticks run per frame, not how much game time a tick covers. So
+/// physics, cooldowns, timers and the bot AI all speed up and slow down
+/// together, and the match plays out the same at any speed. Everything else,
+/// like the camera, keeps running at normal speed.
+#[derive(Resource)]
+pub struct TimeControls {
+    pub speed: f32,
+}
+
+impl Default for TimeControls {
+    fn default() -> Self {
+        Self { speed: 1.0 }
+    }
+}
+
+impl TimeControls {
+    pub fn change_speed(key: Res<Input<KeyCode>>, mut controls: ResMut<Self>) {
+        let speed = controls.speed;
+        let new_speed = if key.just_pressed(KeyCode::Minus) {
+            SPEEDS.iter().rev().copied().find(|&s| s < speed)
+        } else if key.just_pressed(KeyCode::Equals) {
+            SPEEDS.iter().copied().find(|&s| s > speed)
+        } else if key.just_pressed(KeyCode::Key0) {
+            Some(1.0)
+        } else {
+            None
+        };
+        if let Some(new_speed) = new_speed {
+            controls.speed = new_speed;
+        }
+    }
+
+    /// Must run before `FixedUpdate`.
+    pub fn apply_speed(
+        controls: Res<Self>,
+        config: Res<GameConfig>,
+        mut fixed_time: ResMut<Time<Fixed>>,
+    ) {
+        let seconds = config.tick_seconds() / controls.speed;
+        fixed_time.set_timestep_seconds(f64::from(seconds));
+    }
+
+    pub fn draw(state: Res<State<GameState>>, controls: Res<Self>, mut contexts: EguiContexts) {
+        let paused = *state.get() == GameState::Paused;
+        let speed = controls.speed;
+        if !paused && speed == 1.0 {
+            return;
+        }
+
+        let text = if paused {
+            "PAUSED".to_string()
+        } else {
+            format!("{speed}x")
+        };
+        egui::Window::new("Speed")
+            .title_bar(false)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(0.0, 0.0))
+            .interactable(false)
+            .resizable(false)
+            .movable(false)
+            .collapsible(false)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.heading(text);
+            });
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+/// Storage for meshes and materials.
+///
+/// This is unavailable when the simulation runs headless, in which case
+/// entities are spawned with only their physical components.
+pub struct Visuals<'a> {
+    pub meshes: &'a mut Assets<Mesh>,
+    pub materials: &'a mut Assets<StandardMaterial>,
+}
+
+impl<'a> Visuals<'a> {
+    pub fn new(
+        meshes: Option<ResMut<'a, Assets<Mesh>>>,
+        materials: Option<ResMut<'a, Assets<StandardMaterial>>>,
+    ) -> Option<Self> {
+        let (Some(meshes), Some(materials)) = (meshes, materials) else {
+            return None;
+        };
+        Some(Self {
+            meshes: meshes.into_inner(),
+            materials: materials.into_inner(),
+        })
+    }
+}
+
+/// Components that make an entity visible.
+///
+/// This is the rendering half of a `PbrBundle`; the transform is provided by
+/// the entity's physical bundle.
+#[derive(Bundle, Default)]
+pub struct VisualBundle {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+    pub visibility: VisibilityBundle,
+}
+
+impl VisualBundle {
+    pub fn new(mesh: Handle<Mesh>, material: Handle<StandardMaterial>) -> Self {
+        Self {
+            mesh,
+            material,
+            visibility: default(),
+        }
+    }
+}
+
+/// The mesh and material shared by many entities.
+#[derive(Clone)]
+pub struct Visual {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+}
+
+impl Visual {
+    pub fn new(mesh: Handle<Mesh>, material: Handle<StandardMaterial>) -> Self {
+        Self { mesh, material }
+    }
+
+    pub fn bundle(&self) -> VisualBundle {
+        VisualBundle::new(self.mesh.clone(), self.material.clone())
+    }
+}